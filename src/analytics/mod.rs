@@ -0,0 +1,217 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    net::{IpAddr, SocketAddr},
+    sync::OnceLock,
+};
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts, HeaderMap},
+};
+
+/// what happened, stored as lowercase text in `events.kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// a page or article was viewed
+    Visit,
+    /// an element with a `track` attribute was clicked
+    Click,
+    /// a game or wasm demo was started, `track="{name}-play"`
+    Play,
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Visit => "visit",
+            EventKind::Click => "click",
+            EventKind::Play => "play",
+        }
+    }
+}
+
+/// coarse device class derived from the user agent, stored in `events.device`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Device {
+    Desktop,
+    Mobile,
+    Tablet,
+    Bot,
+}
+
+impl Device {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Device::Desktop => "desktop",
+            Device::Mobile => "mobile",
+            Device::Tablet => "tablet",
+            Device::Bot => "bot",
+        }
+    }
+
+    pub fn from_user_agent(user_agent: Option<&str>) -> Self {
+        let Some(user_agent) = user_agent.map(|ua| ua.to_lowercase()) else {
+            return Device::Bot;
+        };
+
+        let contains = |needles: &[&str]| needles.iter().any(|n| user_agent.contains(n));
+        if contains(&[
+            "bot", "crawl", "spider", "preview", "curl", "wget", "python", "http",
+        ]) {
+            Device::Bot
+        } else if contains(&["ipad", "tablet"]) || (contains(&["android"]) && !contains(&["mobi"]))
+        {
+            // android tablets leave out the `Mobile` token of android phones
+            Device::Tablet
+        } else if contains(&["mobi", "android", "iphone"]) {
+            Device::Mobile
+        } else {
+            Device::Desktop
+        }
+    }
+}
+
+/// a single tracked event, as written to the `events` table
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub kind: EventKind,
+    pub target: String,
+    pub referrer: Option<String>,
+    pub device: Device,
+    pub session: String,
+    pub timestamp: i64,
+}
+
+impl Event {
+    /// maps a `track` attribute to a click or, for `{name}-play`, a play of `name`
+    pub fn interaction(visitor: &Visitor, action: &str) -> Self {
+        match action.strip_suffix("-play") {
+            Some(game) => visitor.event(EventKind::Play, game),
+            None => visitor.event(EventKind::Click, action),
+        }
+    }
+}
+
+/// the anonymous parts of a request, that analytics is allowed to keep.
+/// the ip address only ever ends up in the salted session hash.
+#[derive(Debug, Clone)]
+pub struct Visitor {
+    pub referrer: Option<String>,
+    pub device: Device,
+    pub session: String,
+}
+
+impl Visitor {
+    /// the forwarding headers are only honoured for requests from a `trusted` proxy,
+    /// anyone else could send them to pose as many visitors
+    pub fn from_headers(headers: &HeaderMap, peer: Option<SocketAddr>, trusted: &[IpAddr]) -> Self {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let user_agent = header(header::USER_AGENT.as_str());
+        let peer = peer.map(|peer| peer.ip());
+        let ip = match peer {
+            Some(peer) if trusted.contains(&peer) => forwarded_ip(header, trusted),
+            _ => None,
+        }
+        .or(peer)
+        .map(|ip| ip.to_string())
+        .unwrap_or_default();
+
+        // navigation inside the site is not a referral
+        let own_host = header(header::HOST.as_str()).and_then(|host| host.split(':').next());
+        let referrer = header(header::REFERER.as_str())
+            .and_then(referrer_host)
+            .filter(|host| Some(host.as_str()) != own_host);
+
+        Visitor {
+            referrer,
+            device: Device::from_user_agent(user_agent),
+            session: session_hash(&ip, user_agent.unwrap_or_default()),
+        }
+    }
+
+    pub fn event(&self, kind: EventKind, target: &str) -> Event {
+        Event {
+            kind,
+            target: target.to_string(),
+            referrer: self.referrer.clone(),
+            device: self.device,
+            session: self.session.clone(),
+            timestamp: time::OffsetDateTime::now_utc().unix_timestamp(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Visitor
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0);
+        Ok(Visitor::from_headers(
+            &parts.headers,
+            peer,
+            trusted_proxies(),
+        ))
+    }
+}
+
+/// proxies allowed to forward the client ip, from the comma separated `TRUSTED_PROXIES`
+fn trusted_proxies() -> &'static [IpAddr] {
+    static PROXIES: OnceLock<Vec<IpAddr>> = OnceLock::new();
+    PROXIES.get_or_init(|| {
+        std::env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|ip| ip.trim().parse().ok())
+            .collect()
+    })
+}
+
+/// the client ip set by the proxy. the last `X-Forwarded-For` entry, that is not one of our
+/// proxies, as every proxy appends the address it got the request from.
+fn forwarded_ip<'a>(
+    header: impl Fn(&str) -> Option<&'a str>,
+    trusted: &[IpAddr],
+) -> Option<IpAddr> {
+    let parse = |ip: &str| ip.trim().parse::<IpAddr>().ok();
+    match header("x-forwarded-for") {
+        Some(forwarded) => forwarded
+            .rsplit(',')
+            .filter_map(parse)
+            .find(|ip| !trusted.contains(ip)),
+        None => header("x-real-ip").and_then(parse),
+    }
+}
+
+/// the host of a referrer url, without scheme, credentials, port and path
+fn referrer_host(referrer: &str) -> Option<String> {
+    let rest = referrer.split_once("://").map(|(_, rest)| rest)?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.to_lowercase();
+    Some(host).filter(|host| !host.is_empty())
+}
+
+/// a daily rotating hash of ip and user agent. the salt only lives in memory,
+/// so a hash can not be traced back to an ip, not even with the database at hand.
+fn session_hash(ip: &str, user_agent: &str) -> String {
+    static SALT: OnceLock<u64> = OnceLock::new();
+    let day = time::OffsetDateTime::now_utc().date();
+
+    let mut hasher = DefaultHasher::new();
+    SALT.get_or_init(rand::random).hash(&mut hasher);
+    day.hash(&mut hasher);
+    ip.hash(&mut hasher);
+    user_agent.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests;
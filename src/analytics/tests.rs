@@ -0,0 +1,122 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+
+use super::{referrer_host, Device, Event, EventKind, Visitor};
+
+const PHONE: &str =
+    "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 Chrome/120.0 Mobile Safari/537.36";
+const ANDROID_TABLET: &str =
+    "Mozilla/5.0 (Linux; Android 13; SM-X710) AppleWebKit/537.36 Chrome/120.0 Safari/537.36";
+
+fn headers(list: &[(&'static str, &str)]) -> HeaderMap {
+    list.iter()
+        .map(|(name, value)| {
+            (
+                HeaderName::from_static(name),
+                HeaderValue::from_str(value).unwrap(),
+            )
+        })
+        .collect()
+}
+
+fn addr(ip: &str) -> SocketAddr {
+    SocketAddr::new(ip.parse().unwrap(), 4000)
+}
+
+fn session(headers: &HeaderMap, peer: &str, trusted: &[IpAddr]) -> String {
+    Visitor::from_headers(headers, Some(addr(peer)), trusted).session
+}
+
+#[test]
+fn referrer_host_is_the_bare_host() {
+    assert_eq!(
+        referrer_host("https://www.Reddit.com/r/rust/comments?x=1"),
+        Some("www.reddit.com".into())
+    );
+    assert_eq!(
+        referrer_host("http://user:pw@localhost:8080#top"),
+        Some("localhost".into())
+    );
+    assert_eq!(
+        referrer_host("android-app://com.slack/"),
+        Some("com.slack".into())
+    );
+    assert_eq!(referrer_host("/relative/path"), None);
+    assert_eq!(referrer_host("https://"), None);
+}
+
+#[test]
+fn own_host_is_no_referrer() {
+    let own = headers(&[
+        ("host", "lommix.com:443"),
+        ("referer", "https://lommix.com/blog"),
+    ]);
+    let other = headers(&[
+        ("host", "lommix.com"),
+        ("referer", "https://news.ycombinator.com/"),
+    ]);
+
+    assert_eq!(Visitor::from_headers(&own, None, &[]).referrer, None);
+    assert_eq!(
+        Visitor::from_headers(&other, None, &[]).referrer,
+        Some("news.ycombinator.com".into())
+    );
+}
+
+#[test]
+fn devices_are_classified_by_user_agent() {
+    let device = |user_agent: &str| Device::from_user_agent(Some(user_agent));
+
+    assert_eq!(device(PHONE), Device::Mobile);
+    assert_eq!(device(ANDROID_TABLET), Device::Tablet);
+    assert_eq!(
+        device("Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) Mobile/15E148"),
+        Device::Mobile
+    );
+    assert_eq!(
+        device("Mozilla/5.0 (iPad; CPU OS 17_0 like Mac OS X) Mobile/15E148"),
+        Device::Tablet
+    );
+    assert_eq!(
+        device("Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0"),
+        Device::Desktop
+    );
+    assert_eq!(
+        device("Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)"),
+        Device::Bot
+    );
+    assert_eq!(device("curl/8.5.0"), Device::Bot);
+    assert_eq!(Device::from_user_agent(None), Device::Bot);
+}
+
+#[test]
+fn play_tracks_become_plays_of_the_game() {
+    let visitor = Visitor::from_headers(&HeaderMap::new(), None, &[]);
+
+    let play = Event::interaction(&visitor, "shepherd-play");
+    assert_eq!(play.kind, EventKind::Play);
+    assert_eq!(play.target, "shepherd");
+
+    let click = Event::interaction(&visitor, "github");
+    assert_eq!(click.kind, EventKind::Click);
+    assert_eq!(click.target, "github");
+}
+
+#[test]
+fn forwarded_ips_are_only_trusted_from_proxies() {
+    let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+    let forwarded = headers(&[("x-forwarded-for", "203.0.113.7, 10.0.0.1")]);
+    let real_ip = headers(&[("x-real-ip", "203.0.113.7")]);
+    let client = session(&HeaderMap::new(), "203.0.113.7", &[]);
+
+    // behind the proxy, the client is the last address the proxies did not add
+    assert_eq!(session(&forwarded, "10.0.0.1", &[proxy]), client);
+    assert_eq!(session(&real_ip, "10.0.0.1", &[proxy]), client);
+
+    // anyone else gets their own address
+    let spoofed = session(&forwarded, "198.51.100.2", &[proxy]);
+    assert_eq!(spoofed, session(&HeaderMap::new(), "198.51.100.2", &[]));
+    assert_ne!(spoofed, client);
+    assert_ne!(session(&forwarded, "10.0.0.1", &[]), client);
+}
@@ -0,0 +1,23 @@
+use std::path::Path;
+
+/// true, if `cached` exists and is not older than `source`
+pub async fn is_fresh(cached: &Path, source: &Path) -> bool {
+    let modified = |path: &Path| {
+        let path = path.to_path_buf();
+        async move { tokio::fs::metadata(path).await?.modified() }
+    };
+
+    match (modified(cached).await, modified(source).await) {
+        (Ok(cached), Ok(source)) => cached >= source,
+        _ => false,
+    }
+}
+
+/// writes a cache file through a temporary file.
+/// concurrent first requests produce the same file, the rename keeps readers from partial files.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    std::fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+    let tmp = path.with_extension(format!("tmp{}", rand::random::<u32>()));
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)
+}
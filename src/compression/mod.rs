@@ -0,0 +1,215 @@
+use std::io::{Read, Write};
+
+use axum::{
+    body::Bytes,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+
+/// content codings, in the order the server prefers them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Brotli,
+    Zstd,
+    Gzip,
+    Identity,
+}
+
+pub const COMPRESSED: [Encoding; 3] = [Encoding::Brotli, Encoding::Zstd, Encoding::Gzip];
+
+impl Encoding {
+    /// the token used in `Accept-Encoding` and `Content-Encoding`
+    pub fn token(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zstd",
+            Encoding::Gzip => "gzip",
+            Encoding::Identity => "identity",
+        }
+    }
+
+    /// file extension of precompressed files
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Zstd => "zst",
+            Encoding::Gzip => "gz",
+            Encoding::Identity => "",
+        }
+    }
+
+    pub fn from_extension(ext: &str) -> Option<Encoding> {
+        COMPRESSED
+            .into_iter()
+            .find(|encoding| encoding.extension() == ext)
+    }
+}
+
+/// quality value the client gave an encoding in `Accept-Encoding`.
+/// identity is acceptable, unless excluded explicitly. malformed values count as 0.
+fn quality(headers: &HeaderMap, encoding: Encoding) -> f32 {
+    let Some(accept) = headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return match encoding {
+            Encoding::Identity => 1.0,
+            _ => 0.0,
+        };
+    };
+
+    let mut wildcard = None;
+    for entry in accept.split(',') {
+        let mut parts = entry.split(';').map(|part| part.trim());
+        let token = parts.next().unwrap_or_default().to_lowercase();
+        let q = match parts.find_map(|param| param.strip_prefix("q=")) {
+            Some(q) => q
+                .parse::<f32>()
+                .ok()
+                .filter(|q| (0.0..=1.0).contains(q))
+                .unwrap_or(0.0),
+            None => 1.0,
+        };
+
+        if token == encoding.token() || (encoding == Encoding::Gzip && token == "x-gzip") {
+            return q;
+        }
+        if token == "*" {
+            wildcard = Some(q);
+        }
+    }
+
+    match (wildcard, encoding) {
+        (Some(q), _) => q,
+        (None, Encoding::Identity) => 1.0,
+        (None, _) => 0.0,
+    }
+}
+
+pub fn accepts(headers: &HeaderMap, encoding: Encoding) -> bool {
+    quality(headers, encoding) > 0.0
+}
+
+/// picks the available encoding with the highest quality, ties go to the server preference.
+/// identity is only the fallback, if no compression is acceptable. its implicit quality
+/// would otherwise beat every compression with a lower explicit one.
+pub fn negotiate(headers: &HeaderMap, available: &[Encoding]) -> Encoding {
+    available
+        .iter()
+        .filter(|encoding| **encoding != Encoding::Identity)
+        .map(|encoding| (*encoding, quality(headers, *encoding)))
+        .filter(|(_, q)| *q > 0.0)
+        .fold(
+            None,
+            |best: Option<(Encoding, f32)>, (encoding, q)| match best {
+                Some((_, best_q)) if best_q >= q => best,
+                _ => Some((encoding, q)),
+            },
+        )
+        .map(|(encoding, _)| encoding)
+        .unwrap_or(Encoding::Identity)
+}
+
+/// text based formats, that shrink noticeably. images and videos are compressed already.
+pub fn is_compressible(mime: &str) -> bool {
+    let mime = mime.split(';').next().unwrap_or_default().trim();
+    mime.starts_with("text/")
+        || mime.ends_with("+xml")
+        || mime.ends_with("+json")
+        || [
+            "application/javascript",
+            "application/json",
+            "application/xml",
+            "application/wasm",
+        ]
+        .contains(&mime)
+}
+
+pub fn compress(bytes: &[u8], encoding: Encoding) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        Encoding::Brotli => {
+            let mut writer = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+            writer.write_all(bytes)?;
+            Ok(writer.into_inner())
+        }
+        Encoding::Zstd => zstd::encode_all(bytes, 19),
+        Encoding::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+            encoder.write_all(bytes)?;
+            encoder.finish()
+        }
+        Encoding::Identity => Ok(bytes.to_vec()),
+    }
+}
+
+pub fn decompress(bytes: &[u8], encoding: Encoding) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    match encoding {
+        Encoding::Brotli => brotli::Decompressor::new(bytes, 4096).read_to_end(&mut out)?,
+        Encoding::Zstd => return zstd::decode_all(bytes),
+        Encoding::Gzip => flate2::read::GzDecoder::new(bytes).read_to_end(&mut out)?,
+        Encoding::Identity => return Ok(bytes.to_vec()),
+    };
+    Ok(out)
+}
+
+/// an in memory response body, compressed once with every encoding
+#[derive(Clone)]
+pub struct Precompressed {
+    mime: &'static str,
+    bodies: Vec<(Encoding, Bytes)>,
+}
+
+impl Precompressed {
+    pub fn new(mime: &'static str, body: impl Into<Bytes>) -> Self {
+        let body = body.into();
+        let mut bodies = COMPRESSED
+            .into_iter()
+            .filter_map(|encoding| {
+                compress(&body, encoding)
+                    .ok()
+                    .map(|compressed| (encoding, Bytes::from(compressed)))
+            })
+            .collect::<Vec<_>>();
+        bodies.push((Encoding::Identity, body));
+
+        Self { mime, bodies }
+    }
+
+    /// the body in the best encoding the client accepts
+    pub fn respond(&self, headers: &HeaderMap) -> Response {
+        let available = self
+            .bodies
+            .iter()
+            .map(|(encoding, _)| *encoding)
+            .collect::<Vec<_>>();
+        let encoding = negotiate(headers, &available);
+        let body = self
+            .bodies
+            .iter()
+            .find(|(candidate, _)| *candidate == encoding)
+            .map(|(_, body)| body.clone())
+            .unwrap_or_default();
+
+        let mut response = (
+            [
+                (header::CONTENT_TYPE, self.mime),
+                (header::VARY, "accept-encoding"),
+            ],
+            body,
+        )
+            .into_response();
+
+        if encoding != Encoding::Identity {
+            response.headers_mut().insert(
+                header::CONTENT_ENCODING,
+                header::HeaderValue::from_static(encoding.token()),
+            );
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests;
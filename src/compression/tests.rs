@@ -0,0 +1,97 @@
+use axum::{
+    body::to_bytes,
+    http::{header, HeaderMap, HeaderValue},
+};
+
+use super::{accepts, decompress, negotiate, quality, Encoding, Precompressed, COMPRESSED};
+
+const ALL: [Encoding; 4] = [
+    Encoding::Brotli,
+    Encoding::Zstd,
+    Encoding::Gzip,
+    Encoding::Identity,
+];
+
+fn accept(value: &str) -> HeaderMap {
+    HeaderMap::from_iter([(
+        header::ACCEPT_ENCODING,
+        HeaderValue::from_str(value).unwrap(),
+    )])
+}
+
+#[test]
+fn highest_quality_wins() {
+    assert_eq!(
+        negotiate(&accept("gzip;q=0.5, br;q=0.9"), &ALL),
+        Encoding::Brotli
+    );
+    assert_eq!(negotiate(&accept("br;q=0.2, gzip"), &ALL), Encoding::Gzip);
+    assert_eq!(negotiate(&accept("zstd, gzip;q=0.8"), &ALL), Encoding::Zstd);
+}
+
+#[test]
+fn ties_go_to_the_server_preference() {
+    assert_eq!(negotiate(&accept("gzip, zstd, br"), &ALL), Encoding::Brotli);
+    assert_eq!(negotiate(&accept("gzip, zstd"), &ALL), Encoding::Zstd);
+}
+
+#[test]
+fn only_available_encodings_are_picked() {
+    let headers = accept("br, gzip;q=0.5");
+    assert_eq!(
+        negotiate(&headers, &[Encoding::Gzip, Encoding::Identity]),
+        Encoding::Gzip
+    );
+    assert_eq!(negotiate(&headers, &[Encoding::Zstd]), Encoding::Identity);
+}
+
+#[test]
+fn identity_is_acceptable_unless_excluded() {
+    assert!(accepts(&HeaderMap::new(), Encoding::Identity));
+    assert!(!accepts(&HeaderMap::new(), Encoding::Gzip));
+    assert!(accepts(&accept("gzip"), Encoding::Identity));
+    assert!(!accepts(&accept("gzip, identity;q=0"), Encoding::Identity));
+    assert!(!accepts(&accept("*;q=0"), Encoding::Identity));
+}
+
+#[test]
+fn wildcard_covers_unlisted_encodings() {
+    let headers = accept("gzip;q=0.3, *;q=0.6");
+    assert_eq!(quality(&headers, Encoding::Brotli), 0.6);
+    assert_eq!(quality(&headers, Encoding::Gzip), 0.3);
+    assert_eq!(negotiate(&headers, &ALL), Encoding::Brotli);
+    assert_eq!(negotiate(&accept("*"), &ALL), Encoding::Brotli);
+    assert_eq!(negotiate(&accept("*, br;q=0"), &ALL), Encoding::Zstd);
+}
+
+#[test]
+fn malformed_quality_counts_as_zero() {
+    assert_eq!(quality(&accept("gzip;q=abc"), Encoding::Gzip), 0.0);
+    assert_eq!(quality(&accept("gzip;q=2"), Encoding::Gzip), 0.0);
+    assert_eq!(
+        negotiate(&accept("gzip;q=abc, br;q=0.1"), &ALL),
+        Encoding::Brotli
+    );
+    assert_eq!(quality(&accept("x-gzip"), Encoding::Gzip), 1.0);
+}
+
+#[tokio::test]
+async fn precompressed_bodies_decode_to_the_original() {
+    let body = "body { color: red; } ".repeat(50);
+    let precompressed = Precompressed::new("text/css", body.clone());
+
+    for encoding in COMPRESSED {
+        let response = precompressed.respond(&accept(encoding.token()));
+        assert_eq!(
+            response.headers()[header::CONTENT_ENCODING],
+            encoding.token()
+        );
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(decompress(&bytes, encoding).unwrap(), body.as_bytes());
+    }
+
+    let response = precompressed.respond(&HeaderMap::new());
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(bytes, body.as_bytes());
+}
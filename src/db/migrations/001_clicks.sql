@@ -0,0 +1,8 @@
+-- the counters of the first tracking, databases from before migrations already have it
+CREATE TABLE IF NOT EXISTS clicks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    page TEXT NOT NULL,
+    count INTEGER DEFAULT 0,
+    date INTEGER DEFAULT 0,
+    UNIQUE(page, date)
+);
@@ -0,0 +1,42 @@
+-- analytics events and their daily rollups. `clicks` is only read from now on,
+-- `daily_counts` merges it with the rollups, legacy `visit: {alias}` and `{game}-play`
+-- names are split into kind and target.
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    target TEXT NOT NULL,
+    referrer TEXT,
+    device TEXT NOT NULL,
+    session TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS events_created_at ON events(created_at);
+
+CREATE TABLE IF NOT EXISTS daily_rollups (
+    day INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    target TEXT NOT NULL,
+    count INTEGER NOT NULL,
+    sessions INTEGER NOT NULL,
+    PRIMARY KEY(day, kind, target)
+);
+
+CREATE VIEW IF NOT EXISTS daily_counts AS
+SELECT day, kind, target, count, sessions FROM daily_rollups
+UNION ALL
+SELECT
+    date AS day,
+    CASE
+        WHEN page LIKE 'visit: %' OR page = 'page visit' THEN 'visit'
+        WHEN page LIKE '%-play' THEN 'play'
+        ELSE 'click'
+    END AS kind,
+    CASE
+        WHEN page LIKE 'visit: %' THEN substr(page, 8)
+        WHEN page = 'page visit' THEN 'home'
+        WHEN page LIKE '%-play' THEN substr(page, 1, length(page) - 5)
+        ELSE page
+    END AS target,
+    count,
+    NULL AS sessions
+FROM clicks;
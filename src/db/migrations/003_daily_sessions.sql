@@ -0,0 +1,12 @@
+-- distinct sessions per day across all targets. summing the per target sessions of
+-- `daily_rollups` counts a visitor once for every page they saw.
+CREATE TABLE IF NOT EXISTS daily_sessions (
+    day INTEGER PRIMARY KEY,
+    sessions INTEGER NOT NULL
+);
+
+INSERT INTO daily_sessions (day, sessions)
+SELECT created_at - created_at % 86400 AS day, COUNT(DISTINCT session)
+FROM events
+WHERE device != 'bot'
+GROUP BY day;
@@ -0,0 +1,265 @@
+use std::{path::Path, time::Duration};
+
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use deadpool::unmanaged::Pool;
+use rusqlite::Connection;
+
+use crate::analytics::Event;
+
+#[cfg(test)]
+mod tests;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// how long a connection waits for a lock, before it fails with `SQLITE_BUSY`
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// numbered schema migrations, the version of a database is the count of applied ones.
+/// never edit a released migration, add a new one.
+const MIGRATIONS: [&str; 3] = [
+    include_str!("migrations/001_clicks.sql"),
+    include_str!("migrations/002_events.sql"),
+    include_str!("migrations/003_daily_sessions.sql"),
+];
+
+/// a fixed set of sqlite connections. queries block, so they only ever run
+/// on tokio's blocking threads, never on the runtime.
+#[derive(Debug, Clone)]
+pub struct DbPool {
+    pool: Pool<Connection>,
+}
+
+impl From<Vec<Connection>> for DbPool {
+    fn from(connections: Vec<Connection>) -> Self {
+        DbPool {
+            pool: Pool::from(connections),
+        }
+    }
+}
+
+impl DbPool {
+    /// migrates the database at `path` and opens `size` connections to it
+    pub async fn open(path: &Path, size: usize) -> anyhow::Result<Self> {
+        anyhow::ensure!(size > 0, "the pool needs at least one connection");
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connect(&path)?;
+            let applied = migrate(&mut connection)?;
+            if applied > 0 {
+                tracing::info!(
+                    "migrated database to version {}",
+                    schema_version(&connection)?
+                );
+            }
+
+            let connections = (0..size)
+                .map(|_| connect(&path))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(DbPool::from(connections))
+        })
+        .await?
+    }
+
+    /// runs `query` with a pooled connection on a blocking thread
+    pub async fn run<T, F>(&self, query: F) -> anyhow::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> anyhow::Result<T> + Send + 'static,
+    {
+        let Ok(mut con) = self.pool.get().await else {
+            anyhow::bail!("failed to get con from pool");
+        };
+        tokio::task::spawn_blocking(move || query(&mut con)).await?
+    }
+}
+
+/// opens a connection in WAL mode, so readers never block the writer
+pub fn connect(path: &Path) -> anyhow::Result<Connection> {
+    let connection = Connection::open(path)?;
+    connection.busy_timeout(BUSY_TIMEOUT)?;
+    connection.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    connection.pragma_update(None, "synchronous", "NORMAL")?;
+    Ok(connection)
+}
+
+/// the schema version stored in `PRAGMA user_version`, 0 for databases from before migrations
+pub fn schema_version(con: &Connection) -> anyhow::Result<usize> {
+    let version: i64 = con.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    Ok(version as usize)
+}
+
+/// applies all pending migrations, each in its own transaction. returns how many were applied.
+pub fn migrate(con: &mut Connection) -> anyhow::Result<usize> {
+    let version = schema_version(con)?;
+    if version > MIGRATIONS.len() {
+        anyhow::bail!(
+            "database is at version {}, but this build only knows {} migrations",
+            version,
+            MIGRATIONS.len()
+        );
+    }
+
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = con.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+
+    Ok(MIGRATIONS.len() - version)
+}
+
+pub async fn record(pool: &DbPool, event: Event) -> anyhow::Result<()> {
+    pool.run(move |con| {
+        let mut stmt = con.prepare_cached(
+            r#"
+            INSERT INTO events (kind, target, referrer, device, session, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6);
+            "#,
+        )?;
+
+        stmt.execute(rusqlite::params![
+            event.kind.as_str(),
+            event.target,
+            event.referrer,
+            event.device.as_str(),
+            event.session,
+            event.timestamp,
+        ])?;
+        Ok(())
+    })
+    .await
+}
+
+/// recomputes the rollups from the last rolled up day on, which might have been incomplete.
+/// bots are not counted.
+fn rollup(con: &mut Connection) -> anyhow::Result<()> {
+    let tx = con.transaction()?;
+    let since: i64 = tx.query_row(
+        "SELECT COALESCE(MAX(day), 0) FROM daily_rollups",
+        [],
+        |row| row.get(0),
+    )?;
+
+    tx.execute("DELETE FROM daily_rollups WHERE day >= ?1", [since])?;
+    tx.execute(
+        r#"
+        INSERT INTO daily_rollups (day, kind, target, count, sessions)
+        SELECT created_at - created_at % ?2 AS day, kind, target, COUNT(*), COUNT(DISTINCT session)
+        FROM events
+        WHERE created_at >= ?1 AND device != 'bot'
+        GROUP BY day, kind, target;
+        "#,
+        [since, SECONDS_PER_DAY],
+    )?;
+
+    tx.execute("DELETE FROM daily_sessions WHERE day >= ?1", [since])?;
+    tx.execute(
+        r#"
+        INSERT INTO daily_sessions (day, sessions)
+        SELECT created_at - created_at % ?2 AS day, COUNT(DISTINCT session)
+        FROM events
+        WHERE created_at >= ?1 AND device != 'bot'
+        GROUP BY day;
+        "#,
+        [since, SECONDS_PER_DAY],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// the merged count of a target on a single day
+#[derive(Debug, Clone)]
+pub struct DailyCount {
+    pub day: NaiveDate,
+    pub kind: String,
+    pub target: String,
+    pub count: i64,
+    /// unknown for days of the old tracking
+    pub sessions: Option<i64>,
+}
+
+/// the daily counts between `from` and `to`, both inclusive. `pattern` filters targets
+/// like sql `LIKE`, with `*` as wildcard and an implicit one on both ends.
+pub async fn daily_counts(
+    pool: &DbPool,
+    from: NaiveDate,
+    to: NaiveDate,
+    pattern: Option<String>,
+) -> anyhow::Result<Vec<DailyCount>> {
+    let pattern = pattern
+        .map(|pattern| {
+            let escaped = pattern
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+                .replace('*', "%");
+            format!("%{}%", escaped)
+        })
+        .unwrap_or("%".into());
+
+    pool.run(move |con| {
+        rollup(con)?;
+
+        let mut stmt = con.prepare(
+            r#"
+            SELECT day, kind, target, SUM(count), SUM(sessions)
+            FROM daily_counts
+            WHERE day >= ?1 AND day <= ?2 AND target LIKE ?3 ESCAPE '\'
+            GROUP BY day, kind, target
+            ORDER BY day, kind, target;
+            "#,
+        )?;
+
+        let counts = stmt
+            .query_map(
+                rusqlite::params![timestamp(from), timestamp(to), pattern],
+                |row| {
+                    Ok(DailyCount {
+                        day: date(row.get(0)?),
+                        kind: row.get(1)?,
+                        target: row.get(2)?,
+                        count: row.get(3)?,
+                        sessions: row.get(4)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(counts)
+    })
+    .await
+}
+
+/// distinct sessions of every day between `from` and `to` with events, both inclusive
+pub async fn daily_sessions(
+    pool: &DbPool,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> anyhow::Result<Vec<(NaiveDate, i64)>> {
+    pool.run(move |con| {
+        rollup(con)?;
+
+        let mut stmt = con.prepare(
+            "SELECT day, sessions FROM daily_sessions WHERE day >= ?1 AND day <= ?2 ORDER BY day",
+        )?;
+        let sessions = stmt
+            .query_map([timestamp(from), timestamp(to)], |row| {
+                Ok((date(row.get(0)?), row.get(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(sessions)
+    })
+    .await
+}
+
+fn timestamp(day: NaiveDate) -> i64 {
+    day.and_time(NaiveTime::MIN).and_utc().timestamp()
+}
+
+fn date(timestamp: i64) -> NaiveDate {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|date| date.date_naive())
+        .unwrap_or_default()
+}
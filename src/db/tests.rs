@@ -0,0 +1,210 @@
+use rusqlite::Connection;
+
+use chrono::NaiveDate;
+
+use super::{
+    connect, daily_counts, daily_sessions, migrate, rollup, schema_version, DbPool, BUSY_TIMEOUT,
+    MIGRATIONS, SECONDS_PER_DAY,
+};
+use crate::analytics::{Device, Event, EventKind};
+
+/// the only schema, that existed before migrations
+const OLD_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS clicks (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        page TEXT NOT NULL,
+        count INTEGER DEFAULT 0,
+        date INTEGER DEFAULT 0,
+        UNIQUE(page, date)
+    );
+"#;
+
+const DAY: i64 = 1700006400;
+
+fn old_database() -> Connection {
+    let con = Connection::open_in_memory().unwrap();
+    con.execute_batch(OLD_SCHEMA).unwrap();
+    for (page, count) in [
+        ("visit: QuadtreesInRust", 5),
+        ("page visit", 9),
+        ("boid-play", 2),
+    ] {
+        con.execute(
+            "INSERT INTO clicks (page, count, date) VALUES (?1, ?2, ?3)",
+            rusqlite::params![page, count, DAY],
+        )
+        .unwrap();
+    }
+    con
+}
+
+fn tables(con: &Connection) -> Vec<String> {
+    let mut stmt = con
+        .prepare("SELECT name FROM sqlite_master WHERE type IN ('table', 'view') ORDER BY name")
+        .unwrap();
+    stmt.query_map([], |row| row.get(0))
+        .unwrap()
+        .flatten()
+        .collect()
+}
+
+#[test]
+fn fresh_database_is_migrated_to_latest() {
+    let mut con = Connection::open_in_memory().unwrap();
+    assert_eq!(schema_version(&con).unwrap(), 0);
+
+    assert_eq!(migrate(&mut con).unwrap(), MIGRATIONS.len());
+    assert_eq!(schema_version(&con).unwrap(), MIGRATIONS.len());
+
+    let tables = tables(&con);
+    for table in [
+        "clicks",
+        "daily_counts",
+        "daily_rollups",
+        "daily_sessions",
+        "events",
+    ] {
+        assert!(tables.iter().any(|t| t == table), "missing {}", table);
+    }
+}
+
+#[test]
+fn old_schema_is_migrated_forward_with_its_data() {
+    let mut con = old_database();
+    migrate(&mut con).unwrap();
+    assert_eq!(schema_version(&con).unwrap(), MIGRATIONS.len());
+
+    let mut stmt = con
+        .prepare("SELECT kind, target, count FROM daily_counts WHERE day = ?1 ORDER BY target")
+        .unwrap();
+    let counts = stmt
+        .query_map([DAY], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .unwrap()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        counts,
+        vec![
+            ("visit".to_string(), "QuadtreesInRust".to_string(), 5),
+            ("play".to_string(), "boid".to_string(), 2),
+            ("visit".to_string(), "home".to_string(), 9),
+        ]
+    );
+}
+
+#[test]
+fn migrating_twice_is_a_noop() {
+    let mut con = old_database();
+    migrate(&mut con).unwrap();
+    assert_eq!(migrate(&mut con).unwrap(), 0);
+    assert_eq!(schema_version(&con).unwrap(), MIGRATIONS.len());
+}
+
+#[test]
+fn newer_database_is_rejected() {
+    let mut con = Connection::open_in_memory().unwrap();
+    con.pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+        .unwrap();
+    assert!(migrate(&mut con).is_err());
+}
+
+#[tokio::test]
+async fn events_are_rolled_up_next_to_old_counts() {
+    let mut con = old_database();
+    migrate(&mut con).unwrap();
+    let pool = DbPool::from(vec![con]);
+
+    let event = |device, session: &str| Event {
+        kind: EventKind::Visit,
+        target: "QuadtreesInRust".into(),
+        referrer: None,
+        device,
+        session: session.into(),
+        timestamp: DAY + 3600,
+    };
+    for event in [
+        event(Device::Desktop, "a"),
+        event(Device::Mobile, "a"),
+        event(Device::Desktop, "b"),
+        event(Device::Bot, "c"),
+    ] {
+        super::record(&pool, event).await.unwrap();
+    }
+
+    pool.run(rollup).await.unwrap();
+    // refreshing again must not count the same events twice
+    pool.run(rollup).await.unwrap();
+
+    let day = NaiveDate::from_ymd_opt(2023, 11, 15).unwrap();
+    let counts = daily_counts(&pool, day, day, Some("quadtree".into()))
+        .await
+        .unwrap();
+    assert_eq!(counts.len(), 1);
+    assert_eq!(counts[0].kind, "visit");
+    assert_eq!(counts[0].count, 8);
+    assert_eq!(counts[0].sessions, Some(2));
+}
+
+#[tokio::test]
+async fn sessions_are_counted_once_per_day() {
+    let mut con = Connection::open_in_memory().unwrap();
+    migrate(&mut con).unwrap();
+    let pool = DbPool::from(vec![con]);
+
+    let event = |target: &str, session: &str, timestamp| Event {
+        kind: EventKind::Visit,
+        target: target.into(),
+        referrer: None,
+        device: Device::Desktop,
+        session: session.into(),
+        timestamp,
+    };
+    for event in [
+        event("home", "a", DAY),
+        event("blog", "a", DAY + 60),
+        event("boids", "a", DAY + 120),
+        event("home", "b", DAY + 180),
+        event("home", "a", DAY + SECONDS_PER_DAY),
+    ] {
+        super::record(&pool, event).await.unwrap();
+    }
+
+    let day = NaiveDate::from_ymd_opt(2023, 11, 15).unwrap();
+    let sessions = daily_sessions(&pool, day, day.succ_opt().unwrap())
+        .await
+        .unwrap();
+    assert_eq!(sessions, [(day, 2), (day.succ_opt().unwrap(), 1)]);
+}
+
+#[test]
+fn file_databases_are_opened_in_wal_mode() {
+    let path = std::env::temp_dir().join(format!("db-test-{}.db", std::process::id()));
+    let con = connect(&path).unwrap();
+
+    let journal_mode: String = con
+        .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+        .unwrap();
+    let busy_timeout: u64 = con
+        .query_row("PRAGMA busy_timeout", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(journal_mode, "wal");
+    assert_eq!(busy_timeout, BUSY_TIMEOUT.as_millis() as u64);
+
+    drop(con);
+    for suffix in ["", "-wal", "-shm"] {
+        _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
+
+#[tokio::test]
+async fn empty_pools_are_rejected() {
+    let path = std::env::temp_dir().join(format!("db-test-empty-{}.db", std::process::id()));
+    assert!(DbPool::open(&path, 0).await.is_err());
+}
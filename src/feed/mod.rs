@@ -0,0 +1,182 @@
+use axum::{
+    extract::State,
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use maud::{html, Markup, PreEscaped};
+
+use crate::{
+    files::{Article, ArticleStore},
+    meta::{absolute_url, base_url},
+    AppState,
+};
+
+const FEED_TITLE: &str = "Lommix's Blog";
+const FEED_DESCRIPTION: &str = "Gamedev, web wizardry & educational content";
+/// shared by every xml document the site serves
+pub const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="utf-8"?>"#;
+
+/// rss 2.0 feed of all articles, newest first
+pub async fn rss(State(state): State<AppState>) -> Response {
+    let articles = state.articles.read().await;
+    xml_response("application/rss+xml", rss_feed(&articles))
+}
+
+/// atom feed of all articles, newest first
+pub async fn atom(State(state): State<AppState>) -> Response {
+    let articles = state.articles.read().await;
+    xml_response("application/atom+xml", atom_feed(&articles))
+}
+
+fn rss_feed(articles: &ArticleStore) -> Markup {
+    html!(
+        (PreEscaped(XML_DECLARATION))
+        rss version="2.0"
+            xmlns:atom="http://www.w3.org/2005/Atom"
+            xmlns:content="http://purl.org/rss/1.0/modules/content/" {
+            channel {
+                title { (FEED_TITLE) }
+                link { (base_url()) }
+                description { (FEED_DESCRIPTION) }
+                atom:link href=(format!("{}/feed.xml", base_url())) rel="self" type="application/rss+xml" {}
+                @if let Some(latest) = articles.listed().next() {
+                    lastBuildDate { (date_time(latest.meta.published_at).to_rfc2822()) }
+                }
+                @for article in articles.listed() {
+                    item {
+                        title { (article.meta.title) }
+                        link { (article_url(article)) }
+                        guid isPermaLink="true" { (article_url(article)) }
+                        pubDate { (date_time(article.meta.published_at).to_rfc2822()) }
+                        description { (article.meta.teaser) }
+                        @for tag in &article.meta.tag_set {
+                            category { (tag) }
+                        }
+                        @if let Some((url, mime, length)) = cover(article) {
+                            enclosure url=(url) type=(mime) length=(length) {}
+                        }
+                        @if let Some(compiled) = &article.compiled {
+                            content:encoded { (absolute_urls(compiled)) }
+                        }
+                    }
+                }
+            }
+        }
+    )
+}
+
+fn atom_feed(articles: &ArticleStore) -> Markup {
+    let updated = articles
+        .listed()
+        .next()
+        .map(|latest| date_time(latest.meta.published_at))
+        .unwrap_or_default();
+
+    html!(
+        (PreEscaped(XML_DECLARATION))
+        feed xmlns="http://www.w3.org/2005/Atom" xml:base=(base_url()) {
+            id { (format!("{}/", base_url())) }
+            title { (FEED_TITLE) }
+            subtitle { (FEED_DESCRIPTION) }
+            updated { (updated.to_rfc3339()) }
+            link href=(format!("{}/atom.xml", base_url())) rel="self" {}
+            link href=(format!("{}/", base_url())) rel="alternate" type="text/html" {}
+            author { name { "lommix" } }
+            @for article in articles.listed() {
+                entry {
+                    id { (article_url(article)) }
+                    title { (article.meta.title) }
+                    link href=(article_url(article)) rel="alternate" type="text/html" {}
+                    published { (date_time(article.meta.published_at).to_rfc3339()) }
+                    updated { (date_time(article.meta.published_at).to_rfc3339()) }
+                    summary { (article.meta.teaser) }
+                    @for tag in &article.meta.tag_set {
+                        category term=(tag) {}
+                    }
+                    @if let Some((url, mime, length)) = cover(article) {
+                        link rel="enclosure" href=(url) type=(mime) length=(length) {}
+                    }
+                    @if let Some(compiled) = &article.compiled {
+                        content type="html" { (absolute_urls(compiled)) }
+                    }
+                }
+            }
+        }
+    )
+}
+
+fn xml_response(content_type: &'static str, feed: Markup) -> Response {
+    ([(CONTENT_TYPE, content_type)], feed.into_string()).into_response()
+}
+
+fn article_url(article: &Article) -> String {
+    format!("{}/article/{}", base_url(), article.meta.alias)
+}
+
+fn date_time(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(chrono::NaiveTime::MIN).and_utc()
+}
+
+/// resolves root relative urls of links, media and srcsets against the base url.
+/// feed readers show the content outside the site, rss has no way to set a base.
+/// quotes in compiled text are escaped, so every `="` starts an attribute value.
+fn absolute_urls(html: &str) -> String {
+    let root_relative = |url: &str| match url.starts_with('/') && !url.starts_with("//") {
+        true => absolute_url(url),
+        false => url.to_string(),
+    };
+
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(equals) = rest.find("=\"") {
+        let value_start = equals + 2;
+        let value_end = rest[value_start..]
+            .find('"')
+            .map(|i| value_start + i)
+            .unwrap_or(rest.len());
+        let name = rest[..equals]
+            .rsplit(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or_default();
+        let value = &rest[value_start..value_end];
+
+        out.push_str(&rest[..value_start]);
+        match name {
+            "href" | "src" | "poster" => out.push_str(&root_relative(value)),
+            "srcset" => {
+                let candidates = value
+                    .split(',')
+                    .map(|candidate| {
+                        let url = candidate.trim_start();
+                        let space = &candidate[..candidate.len() - url.len()];
+                        format!("{}{}", space, root_relative(url))
+                    })
+                    .collect::<Vec<_>>();
+                out.push_str(&candidates.join(","));
+            }
+            _ => out.push_str(value),
+        }
+        rest = &rest[value_end..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// absolute cover url, mime type and file size, if the cover is one of the article files
+fn cover(article: &Article) -> Option<(String, String, u64)> {
+    let file_name = article.meta.cover.rsplit('/').next()?;
+    let path = article.files.get(file_name)?;
+    let length = std::fs::metadata(path).ok()?.len();
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+
+    Some((
+        format!("{}/media/{}/{}", base_url(), article.meta.alias, file_name),
+        mime.to_string(),
+        length,
+    ))
+}
+
+#[cfg(test)]
+mod tests;
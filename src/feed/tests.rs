@@ -0,0 +1,113 @@
+use chrono::NaiveDate;
+
+use crate::{
+    files::{Article, ArticleMeta, ArticleStore, PublishStatus},
+    meta::base_url,
+};
+
+use super::{absolute_urls, atom_feed, rss_feed};
+
+fn article(alias: &str, published: (i32, u32, u32), html: &str) -> Article {
+    Article {
+        meta: ArticleMeta {
+            title: format!("Title of {}", alias),
+            alias: alias.into(),
+            teaser: "teaser".into(),
+            published_at: NaiveDate::from_ymd_opt(published.0, published.1, published.2).unwrap(),
+            ..Default::default()
+        },
+        compiled: Some(html.into()),
+        ..Default::default()
+    }
+}
+
+fn store() -> ArticleStore {
+    let mut hidden = article("hidden", (2024, 3, 1), "<p>secret</p>");
+    hidden.meta.status = PublishStatus::Unlisted;
+
+    ArticleStore::from(vec![
+        article("older", (2023, 11, 5), "<p>old</p>"),
+        article(
+            "boids",
+            (2024, 2, 12),
+            r#"<p><a href="/article/older">older</a> <a href="https://bevyengine.org/">bevy</a></p><img src="/media/boids/a.png" srcset="/media/boids/a-480.png 480w, /media/boids/a-960.png 960w"><iframe src="/wasm/boids/index.html"></iframe>"#,
+        ),
+        hidden,
+    ])
+}
+
+#[test]
+fn rss_dates_are_rfc2822() {
+    let rss = rss_feed(&store()).into_string();
+    assert!(
+        rss.contains("<lastBuildDate>Mon, 12 Feb 2024 00:00:00 +0000</lastBuildDate>"),
+        "{}",
+        rss
+    );
+    assert!(rss.contains("<pubDate>Sun, 5 Nov 2023 00:00:00 +0000</pubDate>"));
+}
+
+#[test]
+fn atom_dates_are_rfc3339() {
+    let atom = atom_feed(&store()).into_string();
+    assert!(
+        atom.contains("<updated>2024-02-12T00:00:00+00:00</updated>"),
+        "{}",
+        atom
+    );
+    assert!(atom.contains("<published>2023-11-05T00:00:00+00:00</published>"));
+}
+
+#[test]
+fn item_links_are_absolute() {
+    let url = format!("{}/article/boids", base_url());
+
+    let rss = rss_feed(&store()).into_string();
+    assert!(rss.contains(&format!("<link>{}</link>", url)), "{}", rss);
+    assert!(rss.contains(&format!(r#"<guid isPermaLink="true">{}</guid>"#, url)));
+
+    let atom = atom_feed(&store()).into_string();
+    assert!(atom.contains(&format!("<id>{}</id>", url)), "{}", atom);
+    assert!(atom.contains(&format!(r#"<link href="{}" rel="alternate""#, url)));
+}
+
+#[test]
+fn content_links_are_absolute() {
+    let base = base_url();
+    let rss = rss_feed(&store()).into_string();
+    for url in [
+        format!("href=&quot;{}/article/older&quot;", base),
+        format!("src=&quot;{}/media/boids/a.png&quot;", base),
+        format!(
+            "srcset=&quot;{0}/media/boids/a-480.png 480w, {0}/media/boids/a-960.png 960w&quot;",
+            base
+        ),
+        format!("src=&quot;{}/wasm/boids/index.html&quot;", base),
+        "href=&quot;https://bevyengine.org/&quot;".to_string(),
+    ] {
+        assert!(rss.contains(&url), "missing {} in {}", url, rss);
+    }
+}
+
+#[test]
+fn only_root_relative_urls_are_rewritten() {
+    let html = r##"<a href="//cdn.example.com/x" title="/not/a/url">a</a><a href="#top">b</a><a href="/">c</a>"##;
+    assert_eq!(
+        absolute_urls(html),
+        format!(
+            r##"<a href="//cdn.example.com/x" title="/not/a/url">a</a><a href="#top">b</a><a href="{}/">c</a>"##,
+            base_url()
+        )
+    );
+}
+
+#[test]
+fn unlisted_articles_are_left_out() {
+    let rss = rss_feed(&store()).into_string();
+    let atom = atom_feed(&store()).into_string();
+    for feed in [rss, atom] {
+        assert!(!feed.contains("hidden"), "{}", feed);
+        assert!(!feed.contains("secret"));
+        assert!(feed.contains("/article/older"));
+    }
+}
@@ -1,40 +1,156 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use pulldown_cmark::Options;
-use pulldown_cmark::Parser;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use serde::Deserialize;
+use tokio::sync::RwLock;
 
-use crate::pages::PageMeta;
+use crate::markdown::{self, Markdown, MarkdownConfig, MediaContext, TocEntry};
+use crate::search::{SearchHit, SearchIndex};
+use crate::validation::ValidationError;
 
-const ALLOWED_EXTENSIONS: [&str; 12] = [
-    "jpg", "jpeg", "svg", "gz", "png", "gif", "webm", "wasm", "js", "css", "html", "ico",
+/// characters left as they are in a url path segment
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+const ALLOWED_EXTENSIONS: [&str; 14] = [
+    "jpg", "jpeg", "svg", "gz", "br", "zst", "png", "gif", "webm", "wasm", "js", "css", "html",
+    "ico",
 ];
 
-#[derive(Debug)]
-pub struct ArticleStore(Vec<Article>);
+/// article store shared between handlers and the fs watcher
+pub type SharedArticleStore = Arc<RwLock<ArticleStore>>;
+
+#[derive(Debug, Default)]
+pub struct ArticleStore {
+    articles: Vec<Article>,
+    tags: BTreeMap<String, Vec<usize>>,
+    search: SearchIndex,
+}
+
 impl ArticleStore {
     pub fn find_by_alias(&self, alias: &str) -> Option<&Article> {
-        self.0.iter().find(|a| a.meta.alias == alias)
+        self.articles.iter().find(|a| a.meta.alias == alias)
+    }
+
+    /// listed articles with this tag
+    pub fn find_by_tag(&self, tag: &str) -> impl Iterator<Item = &Article> {
+        self.tags
+            .get(&normalize_tag(tag))
+            .into_iter()
+            .flatten()
+            .map(|i| &self.articles[*i])
+            .filter(|article| article.meta.is_listed())
+    }
+
+    /// all tags with their listed article count, sorted by name
+    pub fn tags(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.tags
+            .iter()
+            .map(|(tag, articles)| {
+                let listed = articles
+                    .iter()
+                    .filter(|i| self.articles[**i].meta.is_listed())
+                    .count();
+                (tag.as_str(), listed)
+            })
+            .filter(|(_, count)| *count > 0)
+    }
+
+    /// best matching listed articles for a full-text query
+    pub fn search(&self, query: &str, limit: usize) -> Vec<(&Article, SearchHit)> {
+        self.search
+            .search(query)
+            .into_iter()
+            .map(|hit| (&self.articles[hit.article], hit))
+            .filter(|(article, _)| article.meta.is_listed())
+            .take(limit)
+            .collect()
+    }
+
+    /// all articles, including drafts and unlisted ones
+    pub fn iter(&self) -> std::slice::Iter<'_, Article> {
+        self.articles.iter()
     }
 
-    pub fn iter(&self) -> std::slice::Iter<Article> {
-        self.0.iter()
+    /// articles shown in overviews, feeds, sitemaps and search, newest first.
+    /// evaluated on every call, so scheduled articles show up once they are due.
+    pub fn listed(&self) -> impl Iterator<Item = &Article> {
+        self.articles
+            .iter()
+            .filter(|article| article.meta.is_listed())
     }
 
     pub fn count(&self) -> usize {
-        self.0.len()
+        self.articles.len()
     }
 
     pub async fn from_dir(path: PathBuf) -> anyhow::Result<Self> {
         let mut articles = BlogFsIter::new(path)?.collect::<Vec<_>>();
-        articles.sort_by(|a, b| b.meta.published_at.cmp(&a.meta.published_at));
 
         for article in &mut articles {
             article.compile().await?;
         }
 
-        Ok(Self(articles))
+        Ok(Self::from(articles))
+    }
+
+    /// re-reads and compiles a single article directory, without touching a store.
+    /// `None`, if the directory no longer holds a valid article.
+    pub async fn load(dir: &Path) -> anyhow::Result<Option<Article>> {
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+        match read_article_dir(dir)? {
+            Some(mut article) => {
+                article.compile().await?;
+                Ok(Some(article))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// swaps the article of `dir` for a freshly loaded one, `None` removes it
+    pub fn replace(&mut self, dir: &Path, article: Option<Article>) {
+        self.articles.retain(|a| a.dir != dir);
+        if let Some(article) = article {
+            self.articles.push(article);
+        }
+        self.reindex();
+    }
+
+    pub fn contains_dir(&self, dir: &Path) -> bool {
+        self.articles.iter().any(|a| a.dir == dir)
+    }
+
+    /// sorts articles by date and rebuilds the tag and search index
+    fn reindex(&mut self) {
+        self.articles
+            .sort_by_key(|a| std::cmp::Reverse(a.meta.published_at));
+
+        self.tags.clear();
+        for (i, article) in self.articles.iter().enumerate() {
+            for tag in article.meta.tag_set.iter() {
+                self.tags.entry(tag.clone()).or_default().push(i);
+            }
+        }
+
+        self.search = SearchIndex::build(&self.articles);
+    }
+}
+/// a store of already compiled articles
+impl From<Vec<Article>> for ArticleStore {
+    fn from(articles: Vec<Article>) -> Self {
+        let mut store = Self {
+            articles,
+            ..Default::default()
+        };
+        store.reindex();
+        store
     }
 }
 
@@ -44,6 +160,7 @@ pub struct Article {
     pub source: PathBuf,
     pub dir: PathBuf,
     pub compiled: Option<String>,
+    pub toc: Vec<TocEntry>,
     pub files: HashMap<String, PathBuf>,
 }
 
@@ -52,20 +169,38 @@ impl Article {
         self.source.exists() && !self.meta.title.is_empty() && !self.meta.alias.is_empty()
     }
 
+    pub fn media_context(&self) -> MediaContext<'_> {
+        MediaContext {
+            alias: &self.meta.alias,
+            files: &self.files,
+        }
+    }
+
     pub async fn compile(&mut self) -> anyhow::Result<()> {
-        self.compiled = Some(read_markdown(self.source.clone()).await?);
+        let markdown = read_markdown(
+            self.source.clone(),
+            &self.meta.markdown,
+            &self.media_context(),
+        )
+        .await?;
+
+        for link in markdown.unresolved.iter() {
+            tracing::warn!("{:?}: unresolved link '{}'", self.source, link);
+        }
+
+        self.compiled = Some(markdown.html);
+        self.toc = markdown.toc;
         Ok(())
     }
 }
 
-pub async fn read_markdown(path: PathBuf) -> anyhow::Result<String> {
+pub async fn read_markdown(
+    path: PathBuf,
+    config: &MarkdownConfig,
+    media: &MediaContext<'_>,
+) -> anyhow::Result<Markdown> {
     let raw = tokio::fs::read_to_string(&path).await?;
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    let parser = Parser::new_ext(&raw, options);
-    let mut out = String::new();
-    pulldown_cmark::html::push_html(&mut out, parser);
-    Ok(out)
+    Ok(markdown::compile(&raw, config, media))
 }
 
 #[derive(Deserialize, Clone, Debug, Default)]
@@ -76,20 +211,52 @@ pub struct ArticleMeta {
     pub tags: Option<String>,
     pub published: String,
     pub teaser: String,
+    #[serde(default)]
+    pub status: PublishStatus,
+    #[serde(default)]
+    pub markdown: MarkdownConfig,
 
     #[serde(skip)]
     pub published_at: chrono::NaiveDate,
+    #[serde(skip)]
+    pub tag_set: BTreeSet<String>,
 }
 
-impl Into<PageMeta> for ArticleMeta {
-    fn into(self) -> PageMeta {
-        PageMeta {
-            title: self.title,
-            description: self.teaser,
-            keywords: self.tags.unwrap_or("".into()),
-            image: Some(self.cover),
+/// publication state of an article, set with `status: Draft` in meta.ron
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PublishStatus {
+    /// hidden, only reachable with the preview token
+    Draft,
+    /// hidden until the `published` date has passed
+    Scheduled,
+    /// reachable by alias, but not listed anywhere
+    Unlisted,
+    #[default]
+    Published,
+}
+
+impl ArticleMeta {
+    /// reachable by alias without a preview token
+    pub fn is_public(&self) -> bool {
+        match self.status {
+            PublishStatus::Published | PublishStatus::Unlisted => true,
+            PublishStatus::Scheduled => self.is_due(),
+            PublishStatus::Draft => false,
+        }
+    }
+
+    /// shown in article lists, feeds, sitemaps and search
+    pub fn is_listed(&self) -> bool {
+        match self.status {
+            PublishStatus::Published => true,
+            PublishStatus::Scheduled => self.is_due(),
+            PublishStatus::Unlisted | PublishStatus::Draft => false,
         }
     }
+
+    fn is_due(&self) -> bool {
+        self.published_at <= chrono::Utc::now().date_naive()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -99,53 +266,136 @@ pub struct BlogFsIter {
 
 fn read_blog_path(path: PathBuf) -> anyhow::Result<Vec<Article>> {
     let mut out = Vec::new();
-    let dir = std::fs::read_dir(path)?;
-
-    let mut article = Article::default();
-    dir.into_iter()
-        .flatten()
-        .for_each(|file| match file.path().is_dir() {
-            true => {
-                let _ = read_blog_path(file.path()).and_then(|d| {
-                    out.extend(d);
-                    Ok(())
-                });
-            }
-            false => {
-                // filter for .md file
-                let path = file.path();
-                let extension = match path.extension() {
-                    Some(ext) => ext.to_str().unwrap_or(""),
-                    None => return,
-                };
-
-                match file.file_name().to_str().unwrap_or("") {
-                    "meta.ron" => {
-                        article.meta =
-                            ron::from_str::<ArticleMeta>(&std::fs::read_to_string(path).unwrap())
-                                .expect("Invalid meta.ron");
-                        article.meta.published_at =
-                            chrono::NaiveDate::parse_from_str(&article.meta.published, "%d.%m.%Y")
-                                .expect("Invalid date format");
-                    }
-                    "content.md" => {
-                        article.source = path;
-                    }
-                    _ => {
-                        if ALLOWED_EXTENSIONS.contains(&extension) {
-                            let base_name = path.file_name().unwrap().to_str().unwrap().to_string();
-                            article.files.insert(base_name, path);
-                        }
+
+    for entry in std::fs::read_dir(&path)?.flatten() {
+        if entry.path().is_dir() {
+            out.extend(read_blog_path(entry.path())?);
+        }
+    }
+
+    match read_article_dir(&path) {
+        Ok(Some(article)) => out.push(article),
+        Ok(None) => (),
+        Err(err) => tracing::error!("skipping article: {}", err),
+    }
+
+    Ok(out)
+}
+
+/// reads a single article directory, failing on the first problem found
+fn read_article_dir(path: &Path) -> anyhow::Result<Option<Article>> {
+    let (article, errors) = scan_article_dir(path)?;
+    if let Some(err) = errors.into_iter().next() {
+        return Err(err.into());
+    }
+    Ok(article.valid().then_some(article))
+}
+
+/// reads the files of a single article directory, ignoring subdirectories.
+/// collects every problem with the article instead of stopping at the first one.
+pub fn scan_article_dir(path: &Path) -> anyhow::Result<(Article, Vec<ValidationError>)> {
+    let mut article = Article {
+        dir: path.to_path_buf(),
+        ..Default::default()
+    };
+    let mut errors = Vec::new();
+    let mut has_meta = false;
+    let mut meta_read = false;
+
+    for file in std::fs::read_dir(path)?.flatten() {
+        let path = file.path();
+        if path.is_dir() {
+            continue;
+        }
+
+        let extension = match path.extension() {
+            Some(ext) => ext.to_str().unwrap_or(""),
+            None => continue,
+        };
+
+        match file.file_name().to_str().unwrap_or("") {
+            "meta.ron" => {
+                has_meta = true;
+                match read_meta(&path) {
+                    Ok(meta) => {
+                        article.meta = meta;
+                        meta_read = true;
                     }
+                    Err(err) => errors.push(err),
                 }
             }
-        });
+            "content.md" => {
+                article.source = path;
+            }
+            _ => {
+                if ALLOWED_EXTENSIONS.contains(&extension) {
+                    let base_name = path.file_name().unwrap().to_str().unwrap().to_string();
+                    article.files.insert(base_name, path);
+                }
+            }
+        }
+    }
 
-    if article.valid() {
-        out.push(article);
+    let has_content = article.source.exists();
+    match (has_meta, has_content) {
+        (true, _) => {
+            if meta_read && article.meta.title.is_empty() {
+                errors.push(ValidationError::MissingTitle(path.to_path_buf()));
+            }
+            if meta_read && article.meta.alias.is_empty() {
+                errors.push(ValidationError::MissingAlias(path.to_path_buf()));
+            }
+            if !has_content {
+                errors.push(ValidationError::MissingContent(path.to_path_buf()));
+            }
+        }
+        (false, true) => errors.push(ValidationError::MissingMeta(path.to_path_buf())),
+        (false, false) => (),
     }
 
-    Ok(out)
+    Ok((article, errors))
+}
+
+fn read_meta(path: &Path) -> Result<ArticleMeta, ValidationError> {
+    let raw = std::fs::read_to_string(path).map_err(|err| ValidationError::InvalidMeta {
+        path: path.to_path_buf(),
+        reason: err.to_string(),
+    })?;
+
+    let mut meta =
+        ron::from_str::<ArticleMeta>(&raw).map_err(|err| ValidationError::InvalidMeta {
+            path: path.to_path_buf(),
+            reason: err.to_string(),
+        })?;
+
+    meta.published_at =
+        chrono::NaiveDate::parse_from_str(&meta.published, "%d.%m.%Y").map_err(|_| {
+            ValidationError::InvalidDate {
+                path: path.to_path_buf(),
+                value: meta.published.clone(),
+            }
+        })?;
+
+    meta.tag_set = meta
+        .tags
+        .as_deref()
+        .unwrap_or("")
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(normalize_tag)
+        .filter(|tag| !tag.is_empty())
+        .collect();
+
+    Ok(meta)
+}
+
+/// lowercase tag without leading `#`, so `#Bevy` and `bevy` are the same tag
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// the page of a tag, with the tag percent-encoded
+pub fn tag_url(tag: &str) -> String {
+    format!("/tag/{}", utf8_percent_encode(tag, PATH_SEGMENT))
 }
 
 impl BlogFsIter {
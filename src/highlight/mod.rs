@@ -0,0 +1,74 @@
+use std::{io::Cursor, sync::OnceLock};
+
+use axum::{http::header::CONTENT_TYPE, response::IntoResponse};
+
+use syntect::{
+    highlighting::{Theme, ThemeSet},
+    html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator},
+    parsing::{SyntaxDefinition, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+/// css classes are prefixed to not collide with the page styles
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAX_SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAX_SET.get_or_init(|| {
+        let mut builder = SyntaxSet::load_defaults_newlines().into_builder();
+        for syntax in [
+            include_str!("glsl.sublime-syntax"),
+            include_str!("wgsl.sublime-syntax"),
+        ] {
+            builder.add(
+                SyntaxDefinition::load_from_str(syntax, true, None)
+                    .expect("invalid embedded syntax"),
+            );
+        }
+        builder.build()
+    })
+}
+
+fn theme() -> Theme {
+    ThemeSet::load_from_reader(&mut Cursor::new(include_str!("theme.tmTheme")))
+        .expect("invalid embedded theme")
+}
+
+/// stylesheet for the classes emitted by `highlight`, generated from the embedded theme
+pub async fn stylesheet() -> impl IntoResponse {
+    static CSS: OnceLock<String> = OnceLock::new();
+    let css = CSS.get_or_init(|| {
+        css_for_theme_with_class_style(&theme(), CLASS_STYLE).expect("failed to generate theme css")
+    });
+
+    ([(CONTENT_TYPE, "text/css")], css.as_str())
+}
+
+/// renders a code block as classed html spans.
+/// unknown languages fall back to escaped plain text.
+pub fn highlight(code: &str, lang: &str) -> String {
+    let syntax_set = syntax_set();
+    let lang = lang.split([' ', ',']).next().unwrap_or("");
+
+    let highlighted = syntax_set.find_syntax_by_token(lang).and_then(|syntax| {
+        let mut generator =
+            ClassedHTMLGenerator::new_with_class_style(syntax, syntax_set, CLASS_STYLE);
+        for line in LinesWithEndings::from(code) {
+            generator
+                .parse_html_for_line_which_includes_newline(line)
+                .ok()?;
+        }
+        Some(generator.finalize())
+    });
+
+    let language = match highlighted.is_some() {
+        true => format!(" language-{}", lang),
+        false => String::new(),
+    };
+
+    format!(
+        "<pre><code class=\"hl-code{}\">{}</code></pre>\n",
+        language,
+        highlighted.unwrap_or_else(|| maud::html!((code)).into_string())
+    )
+}
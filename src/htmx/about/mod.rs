@@ -13,10 +13,18 @@ impl HtmxComponent<AppState> for AboutContent {
         "/about"
     }
 
+    fn page() -> Option<&'static str> {
+        Some("/about")
+    }
+
     fn css() -> &'static str {
         include_str!("style.css")
     }
 
+    fn scoped() -> bool {
+        true
+    }
+
     fn handle() -> MethodRouter<AppState> {
         get(|| async move {
             html!{
@@ -0,0 +1,274 @@
+use std::collections::BTreeMap;
+
+use super::HtmxComponent;
+use crate::{
+    db::{self, DailyCount},
+    files::ArticleStore,
+    AppState, ErrorResponse,
+};
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::IntoResponse,
+    routing::{get, MethodRouter},
+};
+use chrono::{Duration, NaiveDate, Utc};
+use maud::{html, Markup};
+use serde::Deserialize;
+
+/// selectable ranges in days
+const RANGES: [i64; 3] = [7, 30, 90];
+const TOP_TARGETS: usize = 10;
+const PAGE: &str = "/admin/stats";
+
+const CHART_WIDTH: f64 = 600.0;
+const CHART_HEIGHT: f64 = 120.0;
+const SPARKLINE_WIDTH: f64 = 120.0;
+const SPARKLINE_HEIGHT: f64 = 24.0;
+
+#[derive(Debug, Deserialize)]
+pub struct RangeQuery {
+    days: Option<i64>,
+}
+
+/// traffic of the last days, behind http basic auth with `ADMIN_PASSWORD`
+pub struct AdminStats;
+impl HtmxComponent<AppState> for AdminStats {
+    fn path() -> &'static str {
+        "/admin/stats"
+    }
+
+    fn page() -> Option<&'static str> {
+        Some(PAGE)
+    }
+
+    fn listed() -> bool {
+        false
+    }
+
+    fn css() -> &'static str {
+        include_str!("style.css")
+    }
+
+    fn scoped() -> bool {
+        true
+    }
+
+    fn handle() -> MethodRouter<AppState> {
+        get(
+            |Query(query): Query<RangeQuery>, headers: HeaderMap, State(state): State<AppState>| async move {
+                if !state.is_admin(&headers) {
+                    return ErrorResponse::Unauthorized.into_response();
+                }
+
+                let days = query.days.unwrap_or(30).clamp(1, 365);
+                let to = Utc::now().date_naive();
+                let from = to - Duration::days(days - 1);
+                let counts = match db::daily_counts(&state.db_pool, from, to, None).await {
+                    Ok(counts) => counts,
+                    Err(err) => {
+                        return ErrorResponse::InternalServerError(err.into()).into_response()
+                    }
+                };
+                let sessions = match db::daily_sessions(&state.db_pool, from, to).await {
+                    Ok(sessions) => sessions.into_iter().map(|(_, sessions)| sessions).sum(),
+                    Err(err) => {
+                        return ErrorResponse::InternalServerError(err.into()).into_response()
+                    }
+                };
+
+                let dashboard =
+                    dashboard(&counts, sessions, from, to, &*state.articles.read().await);
+                ([(header::CACHE_CONTROL, "no-store")], dashboard).into_response()
+            },
+        )
+    }
+}
+
+/// `sessions` are the distinct sessions summed per day
+fn dashboard(
+    counts: &[DailyCount],
+    sessions: i64,
+    from: NaiveDate,
+    to: NaiveDate,
+    articles: &ArticleStore,
+) -> Markup {
+    let days = (to - from).num_days() + 1;
+    let is_visit = |count: &&DailyCount| count.kind == "visit";
+    let is_interaction = |count: &&DailyCount| count.kind != "visit";
+
+    let total = |kind: &str| {
+        counts
+            .iter()
+            .filter(|count| count.kind == kind)
+            .map(|count| count.count)
+            .sum::<i64>()
+    };
+    let daily_visits = per_day(counts.iter().filter(is_visit), from, days);
+    let pages = ranked(counts.iter().filter(is_visit));
+    let targets = ranked(counts.iter().filter(is_interaction));
+
+    html!(
+        div class="admin-stats" {
+            h1 { "Stats" }
+            nav class="ranges" {
+                @for range in RANGES {
+                    a class=[(range == days).then_some("active")]
+                        href=(format!("{}?days={}", PAGE, range))
+                        hx-get=(format!("/htmx/admin/stats?days={}", range))
+                        hx-target="#main"
+                        hx-push-url=(format!("{}?days={}", PAGE, range)) { (range) " days" }
+                }
+            }
+            hr {}
+
+            div class="summary" {
+                (summary("visits", total("visit")))
+                (summary("daily sessions", sessions))
+                (summary("clicks", total("click")))
+                (summary("plays", total("play")))
+            }
+
+            h2 { "Visits per day" }
+            (bar_chart(&daily_visits, from))
+
+            h2 { "Pages" }
+            table {
+                tr { th { "page" } th { "visits" } th { "trend" } }
+                @for (_, target, total) in pages.iter() {
+                    tr {
+                        td { (title(target, articles)) }
+                        td class="number" { (total) }
+                        td {
+                            (sparkline(&per_day(
+                                counts.iter().filter(is_visit).filter(|count| &count.target == target),
+                                from,
+                                days,
+                            )))
+                        }
+                    }
+                }
+            }
+
+            h2 { "Top click targets" }
+            table {
+                tr { th { "target" } th { "kind" } th { "count" } th {} }
+                @let max = targets.first().map(|(_, _, total)| *total).unwrap_or(1);
+                @for (kind, target, total) in targets.iter().take(TOP_TARGETS) {
+                    tr {
+                        td { (target) }
+                        td { (kind) }
+                        td class="number" { (total) }
+                        td { (meter(*total, max)) }
+                    }
+                }
+            }
+        }
+    )
+}
+
+fn summary(label: &str, value: i64) -> Markup {
+    html!(
+        div class="summary-item" {
+            span class="summary-value" { (value) }
+            span class="summary-label" { (label) }
+        }
+    )
+}
+
+/// the article title for article aliases, the target itself for everything else
+fn title(target: &str, articles: &ArticleStore) -> String {
+    articles
+        .find_by_alias(target)
+        .map(|article| article.meta.title.clone())
+        .unwrap_or(target.to_string())
+}
+
+/// the summed count of every day in the range, days without counts are 0
+fn per_day<'a>(
+    counts: impl Iterator<Item = &'a DailyCount>,
+    from: NaiveDate,
+    days: i64,
+) -> Vec<i64> {
+    let mut per_day = vec![0; days as usize];
+    for count in counts {
+        let day = (count.day - from).num_days();
+        if let Some(total) = usize::try_from(day)
+            .ok()
+            .and_then(|day| per_day.get_mut(day))
+        {
+            *total += count.count;
+        }
+    }
+    per_day
+}
+
+/// kind and target with their total, the most counted first
+fn ranked<'a>(counts: impl Iterator<Item = &'a DailyCount>) -> Vec<(String, String, i64)> {
+    let mut totals = BTreeMap::<(&str, &str), i64>::new();
+    for count in counts {
+        *totals.entry((&count.kind, &count.target)).or_default() += count.count;
+    }
+
+    let mut ranked = totals
+        .into_iter()
+        .map(|((kind, target), total)| (kind.to_string(), target.to_string(), total))
+        .collect::<Vec<_>>();
+    ranked.sort_by_key(|(_, _, total)| std::cmp::Reverse(*total));
+    ranked
+}
+
+fn bar_chart(values: &[i64], from: NaiveDate) -> Markup {
+    let max = values.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let slot = CHART_WIDTH / values.len().max(1) as f64;
+    let gap = (slot * 0.2).min(2.0);
+
+    html!(
+        svg class="bar-chart" viewBox=(format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT))
+            preserveAspectRatio="none" role="img" aria-label="visits per day" {
+            @for (i, value) in values.iter().enumerate() {
+                @let height = (*value as f64 / max * CHART_HEIGHT).max(1.0);
+                rect
+                    x=(format!("{:.1}", i as f64 * slot))
+                    y=(format!("{:.1}", CHART_HEIGHT - height))
+                    width=(format!("{:.1}", slot - gap))
+                    height=(format!("{:.1}", height)) {
+                    title { (from + Duration::days(i as i64)) ": " (value) }
+                }
+            }
+        }
+    )
+}
+
+fn sparkline(values: &[i64]) -> Markup {
+    let max = values.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let step = SPARKLINE_WIDTH / (values.len().max(2) - 1) as f64;
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let y = SPARKLINE_HEIGHT - *value as f64 / max * (SPARKLINE_HEIGHT - 2.0) - 1.0;
+            format!("{:.1},{:.1}", i as f64 * step, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    html!(
+        svg class="sparkline" viewBox=(format!("0 0 {} {}", SPARKLINE_WIDTH, SPARKLINE_HEIGHT))
+            preserveAspectRatio="none" aria-hidden="true" {
+            polyline points=(points) {}
+        }
+    )
+}
+
+fn meter(value: i64, max: i64) -> Markup {
+    let width = value as f64 / max.max(1) as f64 * 100.0;
+    html!(
+        svg class="meter" viewBox="0 0 100 8" preserveAspectRatio="none" aria-hidden="true" {
+            rect width=(format!("{:.1}", width)) height="8" {}
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests;
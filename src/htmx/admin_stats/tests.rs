@@ -0,0 +1,151 @@
+use std::sync::Arc;
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::IntoResponse,
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use chrono::NaiveDate;
+use tokio::sync::RwLock;
+
+use super::{bar_chart, meter, per_day, ranked, sparkline};
+use crate::{db::DailyCount, files::ArticleStore, AppState, ErrorResponse, MailerConfig};
+
+fn state(admin_password: Option<&str>) -> AppState {
+    AppState {
+        debug: false,
+        articles: Arc::new(RwLock::new(ArticleStore::from(Vec::new()))),
+        db_pool: Vec::new().into(),
+        mailer: Arc::new(MailerConfig {
+            smtp_user: String::new(),
+            smtp_pass: String::new(),
+            smtp_host: String::new(),
+            mail_to: "to@example.com".parse().unwrap(),
+            mail_from: "from@example.com".parse().unwrap(),
+        }),
+        preview_token: None,
+        admin_password: admin_password.map(String::from),
+    }
+}
+
+fn basic_auth(credentials: &str) -> HeaderMap {
+    let value = format!("Basic {}", BASE64_STANDARD.encode(credentials));
+    HeaderMap::from_iter([(
+        header::AUTHORIZATION,
+        HeaderValue::from_str(&value).unwrap(),
+    )])
+}
+
+fn day(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+}
+
+fn count(day: NaiveDate, kind: &str, target: &str, count: i64) -> DailyCount {
+    DailyCount {
+        day,
+        kind: kind.into(),
+        target: target.into(),
+        count,
+        sessions: None,
+    }
+}
+
+#[test]
+fn admin_needs_the_right_password() {
+    let state = state(Some("secret"));
+    assert!(state.is_admin(&basic_auth("admin:secret")));
+    assert!(state.is_admin(&basic_auth(":secret")));
+    assert!(!state.is_admin(&HeaderMap::new()));
+    assert!(!state.is_admin(&basic_auth("admin:wrong")));
+    assert!(!state.is_admin(&basic_auth("admin:secret2")));
+    assert!(!state.is_admin(&basic_auth("secret")));
+}
+
+#[test]
+fn admin_is_disabled_without_password() {
+    assert!(!state(None).is_admin(&basic_auth("admin:")));
+    assert!(!state(Some("")).is_admin(&basic_auth("admin:")));
+}
+
+#[test]
+fn unauthorized_asks_for_basic_auth() {
+    let response = ErrorResponse::Unauthorized.into_response();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let challenge = response.headers()[header::WWW_AUTHENTICATE]
+        .to_str()
+        .unwrap();
+    assert!(challenge.starts_with("Basic realm="), "{}", challenge);
+}
+
+#[test]
+fn per_day_fills_missing_days_and_skips_outside_ones() {
+    let counts = [
+        count(day(1), "visit", "home", 2),
+        count(day(1), "visit", "blog", 3),
+        count(day(3), "visit", "home", 4),
+        count(day(9), "visit", "home", 7),
+    ];
+    assert_eq!(per_day(counts.iter(), day(1), 4), [5, 0, 4, 0]);
+    assert_eq!(per_day(counts.iter(), day(2), 2), [0, 4]);
+}
+
+#[test]
+fn ranked_keeps_kinds_of_the_same_target_apart() {
+    let counts = [
+        count(day(1), "click", "boids", 2),
+        count(day(2), "click", "boids", 2),
+        count(day(1), "play", "boids", 3),
+        count(day(1), "click", "github", 1),
+    ];
+    assert_eq!(
+        ranked(counts.iter()),
+        [
+            ("click".to_string(), "boids".to_string(), 4),
+            ("play".to_string(), "boids".to_string(), 3),
+            ("click".to_string(), "github".to_string(), 1),
+        ]
+    );
+}
+
+#[test]
+fn bar_chart_scales_to_the_highest_day() {
+    let chart = bar_chart(&[0, 5, 10], day(1)).into_string();
+    assert_eq!(chart.matches("<rect").count(), 3);
+    // empty days keep a visible stub, the highest fills the chart
+    assert!(
+        chart.contains(r#"y="119.0" width="198.0" height="1.0""#),
+        "{}",
+        chart
+    );
+    assert!(
+        chart.contains(r#"y="60.0" width="198.0" height="60.0""#),
+        "{}",
+        chart
+    );
+    assert!(
+        chart.contains(r#"y="0.0" width="198.0" height="120.0""#),
+        "{}",
+        chart
+    );
+    assert!(chart.contains("2024-03-03: 10"), "{}", chart);
+}
+
+#[test]
+fn sparkline_spans_the_full_width() {
+    let line = sparkline(&[0, 4, 2]).into_string();
+    assert!(
+        line.contains(r#"points="0.0,23.0 60.0,1.0 120.0,12.0""#),
+        "{}",
+        line
+    );
+
+    // a single value must not divide by zero
+    let single = sparkline(&[3]).into_string();
+    assert!(single.contains(r#"points="0.0,1.0""#), "{}", single);
+}
+
+#[test]
+fn meter_is_relative_to_the_max() {
+    assert!(meter(5, 20).into_string().contains(r#"width="25.0""#));
+    assert!(meter(0, 0).into_string().contains(r#"width="0.0""#));
+}
@@ -1,12 +1,16 @@
 use super::HtmxComponent;
-use crate::{db, AppState};
+use crate::{
+    analytics::{EventKind, Visitor},
+    db,
+    markdown::TocEntry,
+    AppState, ErrorResponse, PreviewQuery,
+};
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
     response::IntoResponse,
     routing::get,
 };
-use maud::{html, PreEscaped};
+use maud::{html, Markup, PreEscaped};
 
 pub struct ArticleDetail;
 impl HtmxComponent<AppState> for ArticleDetail {
@@ -17,22 +21,71 @@ impl HtmxComponent<AppState> for ArticleDetail {
     fn path() -> &'static str {
         "/article/:alias"
     }
+
+    fn detail_page() -> Option<&'static str> {
+        Some(Self::path())
+    }
     fn handle() -> axum::routing::MethodRouter<AppState> {
         get(
-            |Path(alias): Path<String>, State(state): State<AppState>| async move {
-                match state
+            |Path(alias): Path<String>,
+             Query(query): Query<PreviewQuery>,
+             State(state): State<AppState>,
+             visitor: Visitor| async move {
+                let preview = state.is_preview(query.preview.as_deref());
+                let article = state
                     .articles
+                    .read()
+                    .await
                     .find_by_alias(&alias)
-                    .map(|article| article.compiled.as_ref())
-                    .flatten()
-                {
-                    Some(content) => {
-                        _ = db::inc(&state.db_pool, &format!("visit: {}", alias)).await;
-                        html!(div class="article" {(PreEscaped(content))}).into_response()
+                    .filter(|article| article.meta.is_public() || preview)
+                    .and_then(|article| {
+                        Some((
+                            article.meta.status,
+                            article.compiled.clone()?,
+                            article.toc.clone(),
+                        ))
+                    });
+
+                match article {
+                    Some((status, content, toc)) => {
+                        if !preview {
+                            _ = db::record(&state.db_pool, visitor.event(EventKind::Visit, &alias))
+                                .await;
+                        }
+                        html!(
+                            @if preview {
+                                div class="preview-banner" { "Preview: " (format!("{:?}", status)) }
+                            }
+                            div class="article-layout" {
+                                @if toc.len() > 1 {
+                                    nav class="toc" {
+                                        h4 { "Contents" }
+                                        (toc_list(&toc))
+                                    }
+                                }
+                                div class="article" {(PreEscaped(content))}
+                            }
+                        )
+                        .into_response()
                     }
-                    None => (StatusCode::NOT_FOUND, "Not found").into_response(),
+                    None => ErrorResponse::FileNotFound.into_response(),
                 }
             },
         )
     }
 }
+
+fn toc_list(entries: &[TocEntry]) -> Markup {
+    html!(
+        ul {
+            @for entry in entries {
+                li {
+                    a href=(format!("#{}", entry.id)) { (entry.title) }
+                    @if !entry.children.is_empty() {
+                        (toc_list(&entry.children))
+                    }
+                }
+            }
+        }
+    )
+}
@@ -1,4 +1,7 @@
-use crate::AppState;
+use crate::{
+    files::{tag_url, ArticleMeta},
+    AppState,
+};
 
 use super::HtmxComponent;
 use axum::{
@@ -6,7 +9,7 @@ use axum::{
     response::IntoResponse,
     routing::{get, MethodRouter},
 };
-use maud::html;
+use maud::{html, Markup};
 
 pub struct ArticleList;
 impl HtmxComponent<AppState> for ArticleList {
@@ -21,9 +24,9 @@ impl HtmxComponent<AppState> for ArticleList {
     fn handle() -> MethodRouter<AppState> {
         get(
             |Path((limit, offset)): Path<(usize, usize)>, State(state): State<AppState>| async move {
-                let articles = state
-                    .articles
-                    .iter()
+                let store = state.articles.read().await;
+                let articles = store
+                    .listed()
                     .skip(offset)
                     .enumerate()
                     .map_while(|(i, article)| {
@@ -34,35 +37,13 @@ impl HtmxComponent<AppState> for ArticleList {
                     })
                     .collect::<Vec<_>>();
 
-                if articles.len() == 0 {
+                if articles.is_empty() {
                     return "".into_response();
                 }
 
                 let html = html! {
                     @for meta in articles{
-                        div class="article-preview" {
-                            a
-                                track=(meta.alias)
-                                href=(format!("/article/{}", meta.alias))
-                                hx-push-url=(format!("/article/{}", meta.alias))
-                                hx-get=(format!("/htmx/article/{}", meta.alias))
-                                hx-target="#main"
-                                {
-                                    image src=(format!("/{}",meta.cover));
-                                };
-                            div {
-                                a
-                                    track=(meta.alias)
-                                    href=(format!("/article/{}", meta.alias))
-                                    hx-push-url=(format!("/article/{}", meta.alias))
-                                    hx-get=(format!("/htmx/article/{}", meta.alias))
-                                    hx-target="#main"
-                                    {
-                                    h2 { (meta.title) }
-                                }
-                                p { (meta.teaser) };
-                            };
-                        }
+                        (article_preview(meta))
                     }
 
                     div hx-trigger="revealed" hx-get=(format!("/htmx/articles/3/{}", offset + 3)) hx-swap="outerHTML" {
@@ -75,3 +56,51 @@ impl HtmxComponent<AppState> for ArticleList {
         )
     }
 }
+
+/// preview card linking to the article, shared with other article listings
+pub fn article_preview(meta: &ArticleMeta) -> Markup {
+    html! {
+        div class="article-preview" {
+            a
+                track=(meta.alias)
+                href=(format!("/article/{}", meta.alias))
+                hx-push-url=(format!("/article/{}", meta.alias))
+                hx-get=(format!("/htmx/article/{}", meta.alias))
+                hx-target="#main"
+                {
+                    image src=(format!("/{}",meta.cover));
+                };
+            div {
+                a
+                    track=(meta.alias)
+                    href=(format!("/article/{}", meta.alias))
+                    hx-push-url=(format!("/article/{}", meta.alias))
+                    hx-get=(format!("/htmx/article/{}", meta.alias))
+                    hx-target="#main"
+                    {
+                    h2 { (meta.title) }
+                }
+                p { (meta.teaser) };
+                div class="article-tags" {
+                    @for tag in &meta.tag_set {
+                        (tag_link(tag))
+                    }
+                }
+            };
+        }
+    }
+}
+
+/// link to the listing of all articles with this tag
+pub fn tag_link(tag: &str) -> Markup {
+    html! {
+        a
+            class="tag"
+            track=(format!("tag: {}", tag))
+            href=(tag_url(tag))
+            hx-push-url=(tag_url(tag))
+            hx-get=(format!("/htmx{}", tag_url(tag)))
+            hx-target="#main"
+            { "#" (tag) }
+    }
+}
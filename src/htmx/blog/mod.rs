@@ -12,6 +12,10 @@ impl HtmxComponent<AppState> for BlogContent {
         "/blog"
     }
 
+    fn page() -> Option<&'static str> {
+        Some("/blog")
+    }
+
     fn css() -> &'static str {
         include_str!("style.css")
     }
@@ -21,6 +25,8 @@ impl HtmxComponent<AppState> for BlogContent {
             html!(
                 h1 {"Follow my recent development adventures"}
                 hr{}
+                div hx-get="/htmx/search" hx-trigger="load" {}
+                div hx-get="/htmx/tags" hx-trigger="load" {}
                 div hx-get="/htmx/articles/3/0" hx-trigger="load" {
                     div class="loading-spinner" src="static/images/spinner.svg" {}
                 }
@@ -0,0 +1,253 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    iter::Peekable,
+    str::Chars,
+    sync::OnceLock,
+};
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue},
+    response::Response,
+};
+
+use crate::compression::Precompressed;
+
+/// hashed urls never change their content
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// public urls of the mounted bundles
+struct BundleUrls {
+    style: String,
+    script: String,
+}
+
+static URLS: OnceLock<BundleUrls> = OnceLock::new();
+
+/// the fingerprinted stylesheet url, falls back to the plain url before the router is mounted
+pub fn style_url() -> &'static str {
+    URLS.get()
+        .map(|urls| urls.style.as_str())
+        .unwrap_or("/htmx/style.css")
+}
+
+/// the fingerprinted script url, falls back to the plain url before the router is mounted
+pub fn script_url() -> &'static str {
+    URLS.get()
+        .map(|urls| urls.script.as_str())
+        .unwrap_or("/htmx/script.js")
+}
+
+/// publishes the bundle urls for the templates, the first mounted router wins
+pub fn publish(prefix: &str, style: &Bundle, script: &Bundle) {
+    let _ = URLS.set(BundleUrls {
+        style: format!("{}{}", prefix, style.path),
+        script: format!("{}{}", prefix, script.path),
+    });
+}
+
+/// a minified and fingerprinted asset, served at `/{name}.{hash}.{ext}`
+pub struct Bundle {
+    pub path: String,
+    body: Precompressed,
+}
+
+impl Bundle {
+    pub fn css(source: &str) -> Self {
+        Self::new("style", "css", "text/css", minify_css(source))
+    }
+
+    pub fn js(source: &str) -> Self {
+        Self::new("script", "js", "application/javascript", minify_js(source))
+    }
+
+    fn new(name: &str, ext: &str, mime: &'static str, minified: String) -> Self {
+        Self {
+            path: format!("/{}.{}.{}", name, fingerprint(&minified), ext),
+            body: Precompressed::new(mime, minified),
+        }
+    }
+
+    /// responds with the bundle, `immutable` for requests to the hashed path
+    pub fn respond(&self, headers: &HeaderMap, immutable: bool) -> Response {
+        let mut response = self.body.respond(headers);
+        let cache_control = match immutable {
+            true => IMMUTABLE,
+            false => "no-cache",
+        };
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(cache_control),
+        );
+        response
+    }
+}
+
+pub(super) fn fingerprint(content: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())[..10].to_string()
+}
+
+/// strips comments and whitespace. strings are kept as they are.
+/// spaces before `:` are kept, as they are descendant combinators in selectors.
+fn minify_css(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut pending_space = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            '"' | '\'' => {
+                if pending_space && !after_token(&out) {
+                    out.push(' ');
+                }
+                pending_space = false;
+                out.push(c);
+                while let Some(inner) = chars.next() {
+                    out.push(inner);
+                    match inner {
+                        '\\' => out.extend(chars.next()),
+                        inner if inner == c => break,
+                        _ => (),
+                    }
+                }
+            }
+            c if c.is_whitespace() => pending_space = !out.is_empty(),
+            '{' | '}' | ';' | ',' | '>' => {
+                if c == '}' && out.ends_with(';') {
+                    out.pop();
+                }
+                out.push(c);
+                pending_space = false;
+            }
+            c => {
+                if pending_space && !after_token(&out) {
+                    out.push(' ');
+                }
+                out.push(c);
+                pending_space = false;
+            }
+        }
+    }
+
+    out
+}
+
+/// no space is needed after punctuation
+fn after_token(out: &str) -> bool {
+    out.chars()
+        .last()
+        .map(|last| "{};,>:".contains(last))
+        .unwrap_or(true)
+}
+
+/// strips comments and collapses whitespace outside of strings, template literals and
+/// their `${}` expressions. line breaks are kept, so automatic semicolon insertion still works.
+fn minify_js(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut pending = Whitespace::None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|c| *c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+                pending = pending.max(Whitespace::Space);
+            }
+            c if c.is_whitespace() => {
+                let found = match c {
+                    '\n' => Whitespace::Newline,
+                    _ => Whitespace::Space,
+                };
+                pending = pending.max(found);
+            }
+            c => {
+                match pending {
+                    _ if out.is_empty() => (),
+                    Whitespace::Newline => out.push('\n'),
+                    Whitespace::Space => out.push(' '),
+                    Whitespace::None => (),
+                }
+                pending = Whitespace::None;
+                copy_js_token(c, &mut chars, &mut out);
+            }
+        }
+    }
+
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// whitespace skipped since the last token, a line break wins over spaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Whitespace {
+    None,
+    Space,
+    Newline,
+}
+
+/// copies a character, an escape or a whole string or template literal starting with it, as it is
+fn copy_js_token(c: char, chars: &mut Peekable<Chars>, out: &mut String) {
+    out.push(c);
+    if c == '\\' {
+        // escaped slashes of regex literals do not start comments
+        out.extend(chars.next());
+        return;
+    }
+    if !matches!(c, '"' | '\'' | '`') {
+        return;
+    }
+
+    while let Some(inner) = chars.next() {
+        out.push(inner);
+        match inner {
+            '\\' => out.extend(chars.next()),
+            inner if inner == c => return,
+            '$' if c == '`' && chars.peek() == Some(&'{') => {
+                out.extend(chars.next());
+                copy_js_expression(chars, out);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// copies a `${}` expression up to and including its closing brace
+fn copy_js_expression(chars: &mut Peekable<Chars>, out: &mut String) {
+    let mut depth = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => {
+                out.push(c);
+                return;
+            }
+            '}' => depth -= 1,
+            _ => (),
+        }
+        copy_js_token(c, chars, out);
+    }
+}
+
+#[cfg(test)]
+mod tests;
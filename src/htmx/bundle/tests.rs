@@ -0,0 +1,47 @@
+use super::{minify_css, minify_js};
+
+#[test]
+fn css_comments_and_whitespace_are_stripped() {
+    let source =
+        "/* theme */\n.card > a ,\n.card :hover {\n    color: red ;\n    margin: 0 auto;\n}\n";
+    assert_eq!(
+        minify_css(source),
+        ".card>a,.card :hover{color:red;margin:0 auto}"
+    );
+}
+
+#[test]
+fn css_strings_are_kept() {
+    let source = ".quote::before { content: \"  /* not a comment */ \"; }\n.x { font-family: 'Open  Sans'; }";
+    assert_eq!(
+        minify_css(source),
+        ".quote::before{content:\"  /* not a comment */ \"}.x{font-family:'Open  Sans'}"
+    );
+}
+
+#[test]
+fn js_comments_and_indentation_are_stripped() {
+    let source = "// setup\nfunction add(a, b) {\n    /* sum */\n    return a + b; // done\n}\n\n\nadd(1,   2)\n";
+    assert_eq!(
+        minify_js(source),
+        "function add(a, b) {\nreturn a + b;\n}\nadd(1, 2)\n"
+    );
+}
+
+#[test]
+fn js_strings_and_template_literals_are_kept() {
+    let source = "const url = \"https://lommix.de\"; // home\n\
+        const html = `\n    <a href=\"//cdn\">\n        ${ items.map(i => `<b>${i}</b>`).join(\"  \") }\n    </a>`;\n\
+        const re = /\\/\\//g;\n";
+    assert_eq!(
+        minify_js(source),
+        "const url = \"https://lommix.de\";\n\
+        const html = `\n    <a href=\"//cdn\">\n        ${ items.map(i => `<b>${i}</b>`).join(\"  \") }\n    </a>`;\n\
+        const re = /\\/\\//g;\n"
+    );
+}
+
+#[test]
+fn empty_js_stays_empty() {
+    assert_eq!(minify_js("// nothing here\n\n"), "");
+}
@@ -19,10 +19,18 @@ impl HtmxComponent<AppState> for ContactContent {
         "/contact"
     }
 
+    fn page() -> Option<&'static str> {
+        Some("/contact")
+    }
+
     fn css() -> &'static str {
         include_str!("style.css")
     }
 
+    fn scoped() -> bool {
+        true
+    }
+
     fn handle() -> MethodRouter<AppState> {
         get(on_get).post(on_post)
     }
@@ -60,16 +68,16 @@ async fn on_post(
     Form(data): Form<ContactData>,
 ) -> Result<Response, ContactError> {
     // honeypot
-    if data.captcha.len() > 0 {
+    if !data.captcha.is_empty() {
         return Err(ContactError::InvalidCaptcha);
     }
 
     // honeypot
-    if data.csrf.len() > 0 {
+    if !data.csrf.is_empty() {
         return Err(ContactError::InvalidCsrf);
     }
 
-    if data.message.len() == 0 {
+    if data.message.is_empty() {
         return Err(ContactError::NoMessage);
     }
 
@@ -47,16 +47,16 @@ async fn on_get() -> Response {
 
 async fn on_post(Form(data): Form<FeedbackData>) -> Result<Response, FeedbackError> {
     // honeypot
-    if data.captcha.len() > 0 {
+    if !data.captcha.is_empty() {
         return Err(FeedbackError::InvalidCaptcha);
     }
 
     // honeypot
-    if data.csrf.len() > 0 {
+    if !data.csrf.is_empty() {
         return Err(FeedbackError::InvalidCsrf);
     }
 
-    if data.message.len() == 0 {
+    if data.message.is_empty() {
         return Err(FeedbackError::NoMessage);
     }
 
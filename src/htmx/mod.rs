@@ -1,21 +1,32 @@
 use axum::{
-    http::header,
+    http::HeaderMap,
     routing::{get, MethodRouter},
     Router,
 };
+use std::sync::Arc;
 
 use crate::AppState;
+use bundle::Bundle;
 
 mod about;
+mod admin_stats;
 mod article_detail;
 mod article_list;
 mod blog;
+pub mod bundle;
 mod contact;
 mod feedback;
 mod home;
+mod scope;
+mod search;
+mod tag;
+mod tag_cloud;
 mod track;
 
-pub(crate) fn htmx_router() -> Router<AppState> {
+/// where the htmx router is nested
+pub const PREFIX: &str = "/htmx";
+
+pub(crate) fn htmx_router() -> HtmxRouter<AppState> {
     HtmxRouter::new()
         .add(article_detail::ArticleDetail)
         .add(article_list::ArticleList)
@@ -25,7 +36,10 @@ pub(crate) fn htmx_router() -> Router<AppState> {
         .add(contact::ContactContent)
         .add(feedback::Feedback)
         .add(track::Track)
-        .into()
+        .add(tag::TagList)
+        .add(tag_cloud::TagCloud)
+        .add(search::Search)
+        .add(admin_stats::AdminStats)
 }
 
 pub struct HtmxRouter<S>
@@ -33,6 +47,8 @@ where
     S: Clone + Sync + Send + 'static,
 {
     router: Router<S>,
+    page_router: Router<S>,
+    pages: Vec<&'static str>,
     css: String,
     js: String,
 }
@@ -44,52 +60,93 @@ where
     pub fn new() -> Self {
         Self {
             router: Router::new(),
+            page_router: Router::new(),
+            pages: Vec::new(),
             css: String::new(),
             js: String::new(),
         }
     }
 
-    pub fn add<T: HtmxComponent<S>>(mut self, comp: T) -> Self {
-        self.css.push_str(T::css());
+    pub fn add<T: HtmxComponent<S>>(mut self, _comp: T) -> Self {
+        let mut handle = T::handle();
+        match T::scoped() {
+            true => {
+                let class = scope::class(T::path());
+                self.css.push_str(&scope::scope_css(T::css(), &class));
+                handle = scope::layer(handle, class);
+            }
+            false => self.css.push_str(T::css()),
+        }
+
         self.js.push_str(T::js());
-        self.router = self.router.route(T::path(), T::handle());
+        for page in T::page().into_iter().chain(T::detail_page()) {
+            self.page_router = self.page_router.route(page, handle.clone());
+        }
+        self.router = self.router.route(T::path(), handle);
+        if T::listed() {
+            self.pages.extend(T::page());
+        }
         self
     }
+
+    /// public page urls of all listed components, that represent a full page
+    pub fn pages(&self) -> &[&'static str] {
+        &self.pages
+    }
+
+    /// the pages and detail pages mounted at their public urls, without the htmx prefix
+    pub fn page_router(&self) -> Router<S> {
+        self.page_router.clone()
+    }
 }
 
-impl<S> Into<Router<S>> for HtmxRouter<S>
+impl<S> From<HtmxRouter<S>> for Router<S>
 where
     S: Clone + Sync + Send + 'static,
 {
-    fn into(self) -> Router<S> {
-        self.router
-            .route(
-                "/script.js",
-                get(|| async {
-                    axum::http::Response::builder()
-                        .header(header::CONTENT_TYPE, "application/javascript")
-                        .body(self.js)
-                        .unwrap()
-                }),
-            )
-            .route(
-                "/style.css",
-                get(|| async {
-                    axum::http::Response::builder()
-                        .header(header::CONTENT_TYPE, "text/css")
-                        .body(self.css)
-                        .unwrap()
-                }),
-            )
+    fn from(htmx: HtmxRouter<S>) -> Self {
+        let js = Arc::new(Bundle::js(&htmx.js));
+        let css = Arc::new(Bundle::css(&htmx.css));
+        bundle::publish(PREFIX, &css, &js);
+
+        // the plain urls stay available for cached pages, that still link them
+        htmx.router
+            .route(&js.path.clone(), bundle_route(js.clone(), true))
+            .route(&css.path.clone(), bundle_route(css.clone(), true))
+            .route("/script.js", bundle_route(js, false))
+            .route("/style.css", bundle_route(css, false))
     }
 }
 
+fn bundle_route<S>(bundle: Arc<Bundle>, immutable: bool) -> MethodRouter<S>
+where
+    S: Clone + Sync + Send + 'static,
+{
+    get(move |headers: HeaderMap| async move { bundle.respond(&headers, immutable) })
+}
+
 pub trait HtmxComponent<S: Clone + Sync + Send + 'static> {
     fn path() -> &'static str;
     fn handle() -> MethodRouter<S>;
+    /// the public url, if this component is a standalone page
+    fn page() -> Option<&'static str> {
+        None
+    }
+    /// the public route of a page per item, like `/article/:alias`. not listed in the sitemap.
+    fn detail_page() -> Option<&'static str> {
+        None
+    }
+    /// whether `page()` is listed in the sitemap
+    fn listed() -> bool {
+        true
+    }
     fn css() -> &'static str {
         ""
     }
+    /// rewrites the css to only apply inside the component, its root element gets the scope class
+    fn scoped() -> bool {
+        false
+    }
     fn js() -> &'static str {
         ""
     }
@@ -0,0 +1,301 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::header,
+    middleware::map_response,
+    response::Response,
+    routing::MethodRouter,
+};
+
+use super::bundle::fingerprint;
+
+/// at-rules, whose blocks contain style rules, that are scoped as well
+const NESTING_AT_RULES: [&str; 4] = ["@media", "@supports", "@container", "@layer"];
+
+/// selectors of the document itself, that can not be scoped to a component
+const DOCUMENT_SELECTORS: [&str; 3] = [":root", "html", "body"];
+
+/// the generated class of a scoped component, derived from its path
+pub fn class(path: &str) -> String {
+    format!("scope-{}", &fingerprint(path)[..6])
+}
+
+/// adds the scope class to the root element of every html response of the route
+pub fn layer<S>(router: MethodRouter<S>, class: String) -> MethodRouter<S>
+where
+    S: Clone + Sync + Send + 'static,
+{
+    router.layer(map_response(move |response: Response| {
+        let class = class.clone();
+        async move { scope_response(response, &class).await }
+    }))
+}
+
+async fn scope_response(response: Response, class: &str) -> Response {
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|mime| mime.starts_with("text/html"))
+        .unwrap_or(false);
+    if !is_html {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+
+    let html = add_root_class(&String::from_utf8_lossy(&bytes), class);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(html))
+}
+
+/// elements without content and closing tag
+const VOID_ELEMENTS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// elements, whose content is not parsed as html
+const RAW_TEXT_ELEMENTS: [&str; 3] = ["script", "style", "textarea"];
+
+/// adds `class` to every top level element of an html fragment
+pub fn add_root_class(html: &str, class: &str) -> String {
+    let mut out = String::with_capacity(html.len() + class.len());
+    let mut depth = 0usize;
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let skip_to = |end: &str| rest.find(end).map(|i| i + end.len()).unwrap_or(rest.len());
+        let after = &rest[1..];
+        let len = if rest.starts_with("<!--") {
+            skip_to("-->")
+        } else if after.starts_with(['!', '?']) {
+            skip_to(">")
+        } else if let Some(close) = after.strip_prefix('/') {
+            // maud writes void elements like `hr {}` as `<hr></hr>`, their opening tag never nested
+            let name = close
+                .split(|c: char| c.is_whitespace() || c == '>')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            if !VOID_ELEMENTS.contains(&name.as_str()) {
+                depth = depth.saturating_sub(1);
+            }
+            skip_to(">")
+        } else if after.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let end = tag_end(rest);
+            let tag = &rest[..end];
+            let name = tag[1..]
+                .split(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+
+            match depth == 0 && tag.ends_with('>') {
+                true => out.push_str(&with_class(tag, class)),
+                false => out.push_str(tag),
+            }
+            rest = &rest[end..];
+
+            let self_closing = tag.ends_with("/>") || VOID_ELEMENTS.contains(&name.as_str());
+            if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                let content = rest.find(&format!("</{}", name)).unwrap_or(rest.len());
+                out.push_str(&rest[..content]);
+                rest = &rest[content..];
+                depth += 1;
+            } else if !self_closing {
+                depth += 1;
+            }
+            continue;
+        } else {
+            1
+        };
+
+        out.push_str(&rest[..len]);
+        rest = &rest[len..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// index after the `>` closing the tag at the start of `html`, skipping quoted attribute values
+fn tag_end(html: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in html.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return i + 1,
+            _ => (),
+        }
+    }
+    html.len()
+}
+
+/// the opening tag with `class` added to its class attribute
+fn with_class(tag: &str, class: &str) -> String {
+    match tag.find(" class=\"") {
+        Some(attr) => {
+            let value = attr + " class=\"".len();
+            format!("{}{} {}", &tag[..value], class, &tag[value..])
+        }
+        None => {
+            let close = match tag.ends_with("/>") {
+                true => tag.len() - 2,
+                false => tag.len() - 1,
+            };
+            format!("{} class=\"{}\"{}", &tag[..close], class, &tag[close..])
+        }
+    }
+}
+
+/// rewrites every selector of a stylesheet to only match inside elements with `class`.
+/// the root element itself matches as well, `:where` keeps the original specificity.
+pub fn scope_css(css: &str, class: &str) -> String {
+    let css = strip_comments(css);
+    let mut out = String::with_capacity(css.len() * 2);
+    scope_block(&css, class, &mut out);
+    out
+}
+
+fn scope_block(css: &str, class: &str, out: &mut String) {
+    let mut rest = css;
+
+    while let Some(open) = find_top_level(rest, &['{', ';']) {
+        let prelude = rest[..open].trim();
+
+        if rest[open..].starts_with(';') {
+            out.push_str(prelude);
+            out.push(';');
+            rest = &rest[open + 1..];
+            continue;
+        }
+
+        let close = matching_brace(rest, open);
+        let inner = &rest[open + 1..close];
+
+        if prelude.starts_with('@') {
+            out.push_str(prelude);
+            out.push('{');
+            match NESTING_AT_RULES
+                .iter()
+                .any(|rule| prelude.starts_with(rule))
+            {
+                true => scope_block(inner, class, out),
+                false => out.push_str(inner),
+            }
+            out.push('}');
+        } else {
+            let selectors = split_top_level(prelude, ',')
+                .into_iter()
+                .map(|selector| scope_selector(selector.trim(), class))
+                .collect::<Vec<_>>();
+            out.push_str(&selectors.join(","));
+            out.push('{');
+            out.push_str(inner);
+            out.push('}');
+        }
+
+        rest = &rest[(close + 1).min(rest.len())..];
+    }
+
+    out.push_str(rest.trim());
+}
+
+fn scope_selector(selector: &str, class: &str) -> String {
+    if DOCUMENT_SELECTORS
+        .iter()
+        .any(|document| selector.starts_with(document))
+    {
+        return selector.to_string();
+    }
+
+    // the scope has to come before a trailing pseudo element
+    let (subject, pseudo_element) = match selector.rfind("::") {
+        Some(i) => selector.split_at(i),
+        None => (selector, ""),
+    };
+
+    format!(
+        ":where(.{class}) {selector},{subject}:where(.{class}){pseudo_element}",
+        class = class,
+        selector = selector,
+        subject = subject,
+        pseudo_element = pseudo_element,
+    )
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        rest = rest[start + 2..]
+            .find("*/")
+            .map(|end| &rest[start + 2 + end + 2..])
+            .unwrap_or("");
+    }
+    out.push_str(rest);
+    out
+}
+
+/// finds the first of `chars` outside of strings and parentheses
+fn find_top_level(css: &str, chars: &[char]) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in css.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth -= 1,
+            (None, c) if depth == 0 && chars.contains(&c) => return Some(i),
+            _ => (),
+        }
+    }
+    None
+}
+
+fn split_top_level(css: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = css;
+    while let Some(i) = find_top_level(rest, &[separator]) {
+        parts.push(&rest[..i]);
+        rest = &rest[i + 1..];
+    }
+    parts.push(rest);
+    parts
+}
+
+/// index of the `}` closing the block opened at `open`
+fn matching_brace(css: &str, open: usize) -> usize {
+    let mut depth = 0;
+    let mut quote = None;
+    for (i, c) in css[open..].char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + i;
+                }
+            }
+            _ => (),
+        }
+    }
+    css.len()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,100 @@
+use super::{add_root_class, scope_css};
+
+#[test]
+fn every_selector_of_a_list_is_scoped() {
+    assert_eq!(
+        scope_css(".card, .card > a { color: red; }", "s"),
+        ":where(.s) .card,.card:where(.s),:where(.s) .card > a,.card > a:where(.s){ color: red; }"
+    );
+}
+
+#[test]
+fn scope_goes_before_pseudo_elements() {
+    assert_eq!(
+        scope_css("a:hover, p::first-line { color: red; }", "s"),
+        ":where(.s) a:hover,a:hover:where(.s),:where(.s) p::first-line,p:where(.s)::first-line{ color: red; }"
+    );
+}
+
+#[test]
+fn commas_inside_functions_do_not_split_selectors() {
+    assert_eq!(
+        scope_css(".a:not(.b, .c) { x: y; }", "s"),
+        ":where(.s) .a:not(.b, .c),.a:not(.b, .c):where(.s){ x: y; }"
+    );
+}
+
+#[test]
+fn rules_inside_media_queries_are_scoped() {
+    assert_eq!(
+        scope_css(
+            "@media (max-width: 600px) { .card { display: none; } }",
+            "s"
+        ),
+        "@media (max-width: 600px){:where(.s) .card,.card:where(.s){ display: none; }}"
+    );
+}
+
+#[test]
+fn document_selectors_and_other_at_rules_are_left_alone() {
+    let css = "/* doc */ :root { --x: 1; } body, .b { margin: 0; } \
+        @font-face { font-family: x; } @import url(\"a,b.css\");";
+    assert_eq!(
+        scope_css(css, "s"),
+        ":root{ --x: 1; }body,:where(.s) .b,.b:where(.s){ margin: 0; }\
+        @font-face{ font-family: x; }@import url(\"a,b.css\");"
+    );
+}
+
+#[test]
+fn class_is_added_to_the_root_element() {
+    assert_eq!(
+        add_root_class(r#"<div><p class="x">a</p></div>"#, "s"),
+        r#"<div class="s"><p class="x">a</p></div>"#
+    );
+    assert_eq!(
+        add_root_class(r#"<div class="card" id="a">b</div>"#, "s"),
+        r#"<div class="s card" id="a">b</div>"#
+    );
+}
+
+#[test]
+fn class_is_added_to_every_top_level_element() {
+    let html = r#"<!-- a -->
+<h1 title="a > b">Title</h1><img src="a.png"><br/>
+<script>if (a <b) { x("<div>") }</script>
+<div><span>nested</span></div>text"#;
+
+    assert_eq!(
+        add_root_class(html, "s"),
+        r#"<!-- a -->
+<h1 title="a > b" class="s">Title</h1><img src="a.png" class="s"><br class="s"/>
+<script class="s">if (a <b) { x("<div>") }</script>
+<div class="s"><span>nested</span></div>text"#
+    );
+}
+
+#[test]
+fn fragments_without_elements_are_unchanged() {
+    assert_eq!(add_root_class("just text", "s"), "just text");
+    assert_eq!(add_root_class("", "s"), "");
+}
+
+#[test]
+fn closed_void_elements_do_not_leak_the_class() {
+    let html = maud::html!(
+        div class="about" {
+            hr {}
+            img src="a.png" {}
+            p { "text" }
+            div { input name="a" {} span { "b" } }
+        }
+        hr {}
+        p { "after" }
+    )
+    .into_string();
+    assert_eq!(
+        add_root_class(&html, "s"),
+        r#"<div class="s about"><hr></hr><img src="a.png"></img><p>text</p><div><input name="a"></input><span>b</span></div></div><hr class="s"></hr><p class="s">after</p>"#
+    );
+}
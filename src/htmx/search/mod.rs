@@ -0,0 +1,91 @@
+use super::HtmxComponent;
+use crate::{search::SnippetPart, AppState};
+use axum::{
+    extract::{Query, State},
+    response::IntoResponse,
+    routing::{get, MethodRouter},
+};
+use maud::{html, Markup};
+use serde::Deserialize;
+
+const MAX_RESULTS: usize = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    q: Option<String>,
+}
+
+pub struct Search;
+impl HtmxComponent<AppState> for Search {
+    fn path() -> &'static str {
+        "/search"
+    }
+
+    fn css() -> &'static str {
+        include_str!("style.css")
+    }
+
+    fn handle() -> MethodRouter<AppState> {
+        get(
+            |Query(query): Query<SearchQuery>, State(state): State<AppState>| async move {
+                match query.q {
+                    Some(q) => results(&state, &q).await.into_response(),
+                    None => search_box().into_response(),
+                }
+            },
+        )
+    }
+}
+
+fn search_box() -> Markup {
+    html!(
+        div class="search" {
+            input
+                class="search-input"
+                type="search"
+                name="q"
+                placeholder="Search articles ..."
+                hx-get="/htmx/search"
+                hx-trigger="keyup changed delay:300ms, search"
+                hx-target="#search-results"
+                {}
+            div id="search-results" {}
+        }
+    )
+}
+
+async fn results(state: &AppState, query: &str) -> Markup {
+    if query.trim().is_empty() {
+        return html!();
+    }
+
+    let store = state.articles.read().await;
+    let hits = store.search(query, MAX_RESULTS);
+
+    html!(
+        @if hits.is_empty() {
+            p class="search-empty" { "Nothing found for '" (query) "'" }
+        }
+        @for (article, hit) in hits {
+            div class="search-result" {
+                a
+                    track=(format!("search: {}", article.meta.alias))
+                    href=(format!("/article/{}", article.meta.alias))
+                    hx-push-url=(format!("/article/{}", article.meta.alias))
+                    hx-get=(format!("/htmx/article/{}", article.meta.alias))
+                    hx-target="#main"
+                    {
+                        h3 { (article.meta.title) }
+                    }
+                p {
+                    @for part in hit.snippet {
+                        @match part {
+                            SnippetPart::Text(text) => (text),
+                            SnippetPart::Match(text) => mark { (text) },
+                        }
+                    }
+                }
+            }
+        }
+    )
+}
@@ -0,0 +1,48 @@
+use super::{article_list::article_preview, HtmxComponent};
+use crate::{files::normalize_tag, AppState, ErrorResponse};
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    routing::{get, MethodRouter},
+};
+use maud::html;
+
+pub struct TagList;
+impl HtmxComponent<AppState> for TagList {
+    fn path() -> &'static str {
+        "/tag/:tag"
+    }
+
+    fn detail_page() -> Option<&'static str> {
+        Some(Self::path())
+    }
+
+    fn css() -> &'static str {
+        include_str!("style.css")
+    }
+
+    fn handle() -> MethodRouter<AppState> {
+        get(
+            |Path(tag): Path<String>, State(state): State<AppState>| async move {
+                let store = state.articles.read().await;
+                let articles = store.find_by_tag(&tag).collect::<Vec<_>>();
+
+                if articles.is_empty() {
+                    return ErrorResponse::FileNotFound.into_response();
+                }
+
+                html!(
+                    div class="tag-list" {
+                        h1 { "Articles tagged #" (normalize_tag(&tag)) }
+                        hr{}
+                        @for article in articles {
+                            (article_preview(&article.meta))
+                        }
+                        div hx-get="/htmx/tags" hx-trigger="load" {}
+                    }
+                )
+                .into_response()
+            },
+        )
+    }
+}
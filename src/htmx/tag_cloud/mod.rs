@@ -0,0 +1,54 @@
+use super::HtmxComponent;
+use crate::{files::tag_url, AppState};
+use axum::{
+    extract::State,
+    response::IntoResponse,
+    routing::{get, MethodRouter},
+};
+use maud::html;
+
+/// font size range of the cloud in rem, scaled by article count
+const MIN_SIZE: f32 = 0.9;
+const MAX_SIZE: f32 = 1.8;
+
+pub struct TagCloud;
+impl HtmxComponent<AppState> for TagCloud {
+    fn path() -> &'static str {
+        "/tags"
+    }
+
+    fn css() -> &'static str {
+        include_str!("style.css")
+    }
+
+    fn handle() -> MethodRouter<AppState> {
+        get(|State(state): State<AppState>| async move {
+            let store = state.articles.read().await;
+            let max_count = store.tags().map(|(_, count)| count).max().unwrap_or(1);
+
+            html!(
+                div class="tag-cloud" {
+                    @for (tag, count) in store.tags() {
+                        a
+                            class="tag-cloud-item"
+                            track=(format!("tag: {}", tag))
+                            style=(format!("font-size: {:.2}rem", font_size(count, max_count)))
+                            href=(tag_url(tag))
+                            hx-push-url=(tag_url(tag))
+                            hx-get=(format!("/htmx{}", tag_url(tag)))
+                            hx-target="#main"
+                            {
+                                "#" (tag)
+                                span class="tag-count" { (count) }
+                            }
+                    }
+                }
+            )
+            .into_response()
+        })
+    }
+}
+
+fn font_size(count: usize, max_count: usize) -> f32 {
+    MIN_SIZE + (MAX_SIZE - MIN_SIZE) * (count as f32 / max_count as f32)
+}
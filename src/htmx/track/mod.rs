@@ -1,11 +1,9 @@
-use axum::{
-    extract::State,
-    response::IntoResponse,
-    routing::{get, post},
-    Json,
-};
+use axum::{extract::State, response::IntoResponse, routing::post, Json};
 
-use crate::{db, AppState};
+use crate::{
+    analytics::{Event, Visitor},
+    db, AppState,
+};
 
 use super::HtmxComponent;
 
@@ -21,8 +19,11 @@ impl HtmxComponent<AppState> for Track {
     }
     fn handle() -> axum::routing::MethodRouter<AppState> {
         post(
-            |State(state): State<AppState>, Json(interaction): Json<Interaction>| async move {
-                _ = db::inc(&state.db_pool, &interaction.action).await;
+            |State(state): State<AppState>,
+             visitor: Visitor,
+             Json(interaction): Json<Interaction>| async move {
+                let event = Event::interaction(&visitor, &interaction.action);
+                _ = db::record(&state.db_pool, event).await;
                 "".into_response()
             },
         )
@@ -0,0 +1,211 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use image::{
+    codecs::{avif::AvifEncoder, jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+    DynamicImage,
+};
+use maud::{html, Markup};
+
+use crate::cache::{is_fresh, write_atomic};
+use serde::Deserialize;
+
+/// widths of the generated variants, larger images are never upscaled
+pub const WIDTHS: [u32; 3] = [480, 960, 1600];
+
+/// article images span the content column, which is capped by the page width
+const SIZES: &str = "(min-width: 1450px) 1200px, 100vw";
+
+/// extensions the pipeline can decode, gifs are left alone to keep their animation
+const RESIZABLE: [&str; 3] = ["jpg", "jpeg", "png"];
+
+/// target format of a variant, `Original` keeps the format of the source file
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Original,
+    Webp,
+    Avif,
+}
+
+impl Format {
+    fn extension<'a>(&self, source: &'a str) -> &'a str {
+        match self {
+            Format::Original => source,
+            Format::Webp => "webp",
+            Format::Avif => "avif",
+        }
+    }
+
+    fn mime(&self) -> Option<&'static str> {
+        match self {
+            Format::Original => None,
+            Format::Webp => Some("image/webp"),
+            Format::Avif => Some("image/avif"),
+        }
+    }
+}
+
+/// query of `/media/:alias/:file?w=960&format=avif`
+#[derive(Deserialize, Default, Debug)]
+pub struct VariantQuery {
+    pub w: Option<u32>,
+    #[serde(default)]
+    pub format: Format,
+}
+
+impl VariantQuery {
+    pub fn is_original(&self) -> bool {
+        self.w.is_none() && self.format == Format::Original
+    }
+
+    /// only the listed widths are generated, to keep the cache bounded
+    pub fn is_valid(&self) -> bool {
+        self.w.map(|w| WIDTHS.contains(&w)).unwrap_or(true)
+    }
+}
+
+pub fn is_resizable(path: &Path) -> bool {
+    extension(path)
+        .map(|ext| RESIZABLE.contains(&ext.as_str()))
+        .unwrap_or(false)
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+fn cache_dir() -> PathBuf {
+    std::env::var("IMAGE_CACHE")
+        .unwrap_or("image_cache".to_string())
+        .into()
+}
+
+/// returns the cached variant of `source`, encoding it on first request
+/// or when the source changed since.
+pub async fn variant(alias: &str, source: &Path, query: &VariantQuery) -> anyhow::Result<PathBuf> {
+    variant_in(&cache_dir(), alias, source, query).await
+}
+
+/// like `variant`, with the variants cached in `cache`
+async fn variant_in(
+    cache: &Path,
+    alias: &str,
+    source: &Path,
+    query: &VariantQuery,
+) -> anyhow::Result<PathBuf> {
+    anyhow::ensure!(query.is_valid(), "unsupported variant width {:?}", query.w);
+    let width = query.w;
+
+    // the full file name, so `cover.jpg` and `cover.png` do not share variants
+    let file_name = source
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let ext = extension(source).unwrap_or_default();
+    let cached = cache.join(alias).join(format!(
+        "{}-{}.{}",
+        file_name,
+        width.map(|w| w.to_string()).unwrap_or("full".to_string()),
+        query.format.extension(&ext)
+    ));
+
+    if is_fresh(&cached, source).await {
+        return Ok(cached);
+    }
+
+    let format = query.format;
+    let (source, target) = (source.to_path_buf(), cached.clone());
+    tokio::task::spawn_blocking(move || encode(&source, &target, width, format)).await??;
+    Ok(cached)
+}
+
+/// content type of a variant file
+pub fn mime(path: &Path, format: Format) -> String {
+    match format.mime() {
+        Some(mime) => mime.to_string(),
+        None => mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .to_string(),
+    }
+}
+
+fn encode(source: &Path, target: &Path, width: Option<u32>, format: Format) -> anyhow::Result<()> {
+    let mut image = image::open(source)?;
+
+    if let Some(width) = width.filter(|width| *width < image.width()) {
+        let height = (image.height() as u64 * width as u64 / image.width() as u64).max(1) as u32;
+        image = image.resize_exact(width, height, FilterType::Lanczos3);
+    }
+
+    let mut bytes = Cursor::new(Vec::new());
+    match (format, extension(source).as_deref()) {
+        (Format::Avif, _) => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(AvifEncoder::new_with_speed_quality(&mut bytes, 10, 70))?,
+        (Format::Webp, _) => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut bytes))?,
+        (Format::Original, Some("png")) => image.write_with_encoder(PngEncoder::new(&mut bytes))?,
+        (Format::Original, _) => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, 82))?,
+    }
+
+    write_atomic(target, &bytes.into_inner())?;
+    Ok(())
+}
+
+/// renders a responsive `<picture>` for an article image.
+/// avif is offered for every image, webp is lossless and only pays off for png sources.
+/// returns `None`, if the file can not be processed.
+pub fn picture(url: &str, path: &Path, alt: &str, title: Option<&str>) -> Option<Markup> {
+    if !is_resizable(path) {
+        return None;
+    }
+
+    let (width, height) = image::image_dimensions(path).ok()?;
+    let widths = WIDTHS
+        .into_iter()
+        .filter(|w| *w < width)
+        .map(Some)
+        .chain([None])
+        .collect::<Vec<_>>();
+
+    let srcset = |format: Option<&str>| {
+        widths
+            .iter()
+            .map(|w| {
+                let query = w
+                    .map(|w| format!("w={}", w))
+                    .into_iter()
+                    .chain(format.map(|format| format!("format={}", format)))
+                    .collect::<Vec<_>>()
+                    .join("&");
+                match query.is_empty() {
+                    true => format!("{} {}w", url, width),
+                    false => format!("{}?{} {}w", url, query, w.unwrap_or(width)),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let is_png = extension(path).as_deref() == Some("png");
+    Some(html! {
+        picture {
+            source type="image/avif" srcset=(srcset(Some("avif"))) sizes=(SIZES);
+            @if is_png {
+                source type="image/webp" srcset=(srcset(Some("webp"))) sizes=(SIZES);
+            }
+            img src=(url) srcset=(srcset(None)) sizes=(SIZES) width=(width) height=(height)
+                alt=(alt) title=[title] loading="lazy" decoding="async";
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests;
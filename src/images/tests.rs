@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+
+use super::{picture, variant_in, Format, VariantQuery};
+
+/// a 1000x500 png
+fn stripes() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("src/images/fixtures/stripes.png")
+}
+
+#[test]
+fn only_listed_widths_are_valid() {
+    let query = |w: Option<u32>| VariantQuery {
+        w,
+        format: Format::Avif,
+    };
+
+    assert!(query(None).is_valid());
+    assert!(query(Some(480)).is_valid());
+    assert!(query(Some(1600)).is_valid());
+    assert!(!query(Some(500)).is_valid());
+    assert!(!query(Some(0)).is_valid());
+}
+
+#[test]
+fn picture_offers_smaller_variants_and_the_original() {
+    let html = picture("/media/a/stripes.png", &stripes(), "Stripes", Some("title"))
+        .unwrap()
+        .into_string();
+
+    let srcset = "/media/a/stripes.png?w=480&amp;format=avif 480w, \
+        /media/a/stripes.png?w=960&amp;format=avif 960w, \
+        /media/a/stripes.png?format=avif 1000w";
+    assert!(html.contains(&format!(r#"<source type="image/avif" srcset="{}""#, srcset)));
+    assert!(html.contains(r#"<source type="image/webp""#));
+
+    let srcset = "/media/a/stripes.png?w=480 480w, \
+        /media/a/stripes.png?w=960 960w, \
+        /media/a/stripes.png 1000w";
+    assert!(html.contains(&format!(r#"srcset="{}""#, srcset)));
+    assert!(html.contains(r#"sizes="(min-width: 1450px) 1200px, 100vw""#));
+    assert!(html.contains(r#"width="1000" height="500""#));
+    assert!(html.contains(r#"alt="Stripes" title="title""#));
+    assert!(html.contains(r#"loading="lazy""#));
+}
+
+#[test]
+fn picture_skips_files_it_can_not_resize() {
+    assert!(picture("/media/a/anim.gif", Path::new("anim.gif"), "", None).is_none());
+}
+
+#[tokio::test]
+async fn variant_is_resized_and_cached() {
+    let cache = std::env::temp_dir().join(format!("images-test-{}", std::process::id()));
+    let alias = "stripes";
+    let query = VariantQuery {
+        w: Some(480),
+        format: Format::Webp,
+    };
+
+    let cached = variant_in(&cache, alias, &stripes(), &query).await.unwrap();
+    assert_eq!(cached, cache.join("stripes/stripes.png-480.webp"));
+    assert_eq!(image::image_dimensions(&cached).unwrap(), (480, 240));
+
+    // a fresh variant is not encoded again
+    let modified = std::fs::metadata(&cached).unwrap().modified().unwrap();
+    assert_eq!(
+        variant_in(&cache, alias, &stripes(), &query).await.unwrap(),
+        cached
+    );
+    assert_eq!(
+        std::fs::metadata(&cached).unwrap().modified().unwrap(),
+        modified
+    );
+
+    let invalid = VariantQuery {
+        w: Some(500),
+        format: Format::Webp,
+    };
+    assert!(variant_in(&cache, alias, &stripes(), &invalid)
+        .await
+        .is_err());
+
+    std::fs::remove_dir_all(&cache).unwrap();
+}
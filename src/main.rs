@@ -1,35 +1,90 @@
 use axum::{
-    extract::{Path, Request, State},
-    http::{
-        header::{self, CONTENT_TYPE},
-        StatusCode,
-    },
+    extract::Request,
+    handler::Handler,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
-    routing::{get, post},
+    routing::get,
     Router,
 };
+use base64::{prelude::BASE64_STANDARD, Engine};
 use clap::Parser;
-use deadpool::unmanaged::Pool;
 use dotenv::dotenv;
-use files::ArticleStore;
+use files::{ArticleStore, SharedArticleStore};
 use lettre::message::Mailbox;
-use std::{error::Error, net::SocketAddr, sync::Arc};
-use tokio::net::TcpListener;
+use serde::Deserialize;
+use std::{error::Error, net::SocketAddr, path::PathBuf, sync::Arc};
+use tokio::{net::TcpListener, sync::RwLock};
 use tower_http::services::{ServeDir, ServeFile};
 
+mod analytics;
+mod cache;
+mod compression;
 mod db;
+mod feed;
 mod files;
+mod highlight;
 mod htmx;
+mod images;
+mod markdown;
+mod media;
+mod meta;
+mod og_image;
 mod pages;
+mod search;
+mod sitemap;
+mod stats;
 mod templates;
+mod validation;
+mod watcher;
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewQuery {
+    pub preview: Option<String>,
+}
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub debug: bool,
-    pub articles: Arc<ArticleStore>,
-    pub db_pool: Pool<rusqlite::Connection>,
+    pub articles: SharedArticleStore,
+    pub db_pool: db::DbPool,
     pub mailer: Arc<MailerConfig>,
+    pub preview_token: Option<String>,
+    pub admin_password: Option<String>,
+}
+
+impl AppState {
+    /// drafts and scheduled articles can be viewed with `?preview={PREVIEW_TOKEN}`
+    pub fn is_preview(&self, token: Option<&str>) -> bool {
+        match (&self.preview_token, token) {
+            (Some(expected), Some(token)) => !expected.is_empty() && expected == token,
+            _ => false,
+        }
+    }
+
+    /// admin pages need http basic auth with `ADMIN_PASSWORD`, any user name is fine
+    pub fn is_admin(&self, headers: &HeaderMap) -> bool {
+        let Some(expected) = self.admin_password.as_deref().filter(|p| !p.is_empty()) else {
+            return false;
+        };
+
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Basic "))
+            .and_then(|credentials| BASE64_STANDARD.decode(credentials.trim()).ok())
+            .and_then(|credentials| String::from_utf8(credentials).ok())
+            .and_then(|credentials| {
+                let (_, password) = credentials.split_once(':')?;
+                Some(constant_time_eq(password.as_bytes(), expected.as_bytes()))
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// compares without returning early, so the time does not leak how much of a secret matched
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
 }
 
 #[derive(Debug, Clone)]
@@ -54,9 +109,32 @@ impl MailerConfig {
 }
 
 #[derive(Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// path of the sqlite database
+    #[arg(long, env = "DB_PATH", default_value = "lommix.db", global = true)]
+    db_path: PathBuf,
+    /// number of pooled database connections
+    #[arg(
+        long,
+        env = "DB_POOL_SIZE",
+        default_value_t = 4,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        global = true
+    )]
+    db_pool_size: usize,
+}
+
+#[derive(clap::Subcommand)]
 enum Command {
     Serve,
-    Stats,
+    /// print analytics, see `stats --help`
+    Stats(stats::StatsArgs),
+    /// apply pending database migrations and exit
+    Migrate,
+    /// validate all articles and exit non-zero on problems
+    Check,
 }
 
 #[tokio::main]
@@ -64,54 +142,102 @@ async fn main() -> anyhow::Result<()> {
     dotenv().ok();
     tracing_subscriber::fmt::fmt().with_target(false).init();
 
-    let http_port: u16 = std::env::var("HTTP_PORT")
-        .expect("HTTP_PORT must be set")
-        .parse()
-        .expect("bad http port");
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Serve => {
+            let http_port: u16 = std::env::var("HTTP_PORT")
+                .expect("HTTP_PORT must be set")
+                .parse()
+                .expect("bad http port");
 
-    let db_pool = db::open_or_create_db().await?;
-    let mailer = Arc::new(MailerConfig::from_env().unwrap());
+            let db_pool = db::DbPool::open(&cli.db_path, cli.db_pool_size).await?;
+            let mailer = Arc::new(MailerConfig::from_env().unwrap());
 
-    let cmd = Command::parse();
-    match cmd {
-        Command::Serve => {
             let state = AppState {
                 debug: true,
-                articles: Arc::new(
+                articles: Arc::new(RwLock::new(
                     ArticleStore::from_dir("blog".into())
                         .await
                         .expect("Failed to load articles"),
-                ),
+                )),
                 db_pool,
                 mailer,
+                preview_token: std::env::var("PREVIEW_TOKEN").ok(),
+                admin_password: std::env::var("ADMIN_PASSWORD").ok(),
             };
 
+            let _watcher = watcher::watch_articles(state.articles.clone(), "blog".into())
+                .expect("Failed to watch articles");
+
             let serve_router = Router::new()
                 .nest_service("/", ServeDir::new("wasm").precompressed_gzip())
                 .layer(axum::middleware::from_fn(no_cache_middle));
 
+            let htmx_router = htmx::htmx_router();
+            let sitemap_pages = htmx_router.pages().to_vec();
+            let layout = axum::middleware::from_fn_with_state(state.clone(), pages::layout);
+            let page_router = htmx_router.page_router().layer(layout.clone());
+
             let router = Router::new()
-                .route("/", get(pages::home))
-                .route("/*page", get(pages::home))
-                .nest("/htmx", htmx::htmx_router())
-                .route("/media/:alias/:file", get(serve_article_media))
+                .route("/feed.xml", get(feed::rss))
+                .route("/atom.xml", get(feed::atom))
+                .route(
+                    "/sitemap.xml",
+                    get(move |state| sitemap::sitemap(state, sitemap_pages.clone())),
+                )
+                .route("/robots.txt", get(sitemap::robots))
+                .route("/highlight.css", get(highlight::stylesheet))
+                .merge(page_router)
+                .nest(
+                    htmx::PREFIX,
+                    Router::from(htmx_router).layer(layout.clone()),
+                )
+                .merge(
+                    Router::new()
+                        .route("/media/:alias/:file", get(media::serve_article_media))
+                        .route(og_image::SITE_URL, get(og_image::site))
+                        .route("/og/:alias", get(og_image::article))
+                        .layer(axum::middleware::map_response(pages::plain_errors)),
+                )
                 .nest_service("/favicon.ico", ServeFile::new("favicon.ico"))
                 .nest_service("/static", ServeDir::new("static").precompressed_gzip())
                 .nest_service("/wasm", serve_router.into_service())
+                .fallback(pages::not_found.layer(layout))
                 .layer(tower_http::trace::TraceLayer::new_for_http())
                 .with_state(state.clone());
 
-            let addr = SocketAddr::from(([127, 0, 0, 1], http_port.clone()));
+            let addr = SocketAddr::from(([127, 0, 0, 1], http_port));
             tracing::info!("Starting server on {}", addr);
 
             let listener = TcpListener::bind(addr).await.unwrap();
-            axum::serve(listener, router).await.unwrap();
+            axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        }
+        Command::Stats(args) => {
+            let db_pool = db::DbPool::open(&cli.db_path, 1).await?;
+            stats::print(&db_pool, args).await?;
+        }
+        Command::Migrate => {
+            let mut connection = db::connect(&cli.db_path)?;
+            let applied = db::migrate(&mut connection)?;
+            println!(
+                "applied {} migrations, database is at version {}",
+                applied,
+                db::schema_version(&connection)?
+            );
         }
-        Command::Stats => {
-            println!("printing stats ...");
-            db::stats(&db_pool).await?.iter().for_each(|stat| {
-                println!("{}", stat);
-            });
+        Command::Check => {
+            let errors = validation::validate("blog".as_ref())?;
+            errors.iter().for_each(|err| println!("{}", err));
+
+            if !errors.is_empty() {
+                anyhow::bail!("found {} problems in articles", errors.len());
+            }
+            println!("all articles are valid");
         }
     };
 
@@ -134,47 +260,21 @@ pub enum ErrorResponse {
 
 impl IntoResponse for ErrorResponse {
     fn into_response(self) -> Response {
-        match self {
-            ErrorResponse::FileNotFound => {
-                (StatusCode::NOT_FOUND, "File not found").into_response()
-            }
-            ErrorResponse::Unauthorized => {
-                (StatusCode::UNAUTHORIZED, "Unauthorized").into_response()
-            }
+        let status = match self {
+            ErrorResponse::FileNotFound => StatusCode::NOT_FOUND,
+            ErrorResponse::Unauthorized => StatusCode::UNAUTHORIZED,
             ErrorResponse::InternalServerError(err) => {
                 tracing::error!("Internal server error: {}", err);
-                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error").into_response()
+                StatusCode::INTERNAL_SERVER_ERROR
             }
+        };
+        let mut response = (status, templates::error(status)).into_response();
+        if status == StatusCode::UNAUTHORIZED {
+            response.headers_mut().insert(
+                header::WWW_AUTHENTICATE,
+                HeaderValue::from_static("Basic realm=\"admin\", charset=\"UTF-8\""),
+            );
         }
+        response
     }
 }
-
-async fn serve_article_media(
-    Path((alias, file)): Path<(String, String)>,
-    State(state): State<AppState>,
-) -> Result<Response, ErrorResponse> {
-    let article = state
-        .articles
-        .iter()
-        .find(|article| article.meta.alias == alias)
-        .ok_or_else(|| ErrorResponse::FileNotFound)?;
-
-    let file_path = article
-        .files
-        .get(&file)
-        .ok_or_else(|| ErrorResponse::FileNotFound)?;
-
-    let file = tokio::fs::File::open(file_path)
-        .await
-        .map_err(|_| ErrorResponse::FileNotFound)?;
-
-    let mime_type = mime_guess::from_path(file_path).first_or_octet_stream();
-    let stream = tokio_util::io::ReaderStream::new(file);
-
-    let response = Response::builder()
-        .header(CONTENT_TYPE, mime_type.to_string())
-        .body(axum::body::Body::from_stream(stream))
-        .map_err(|err| ErrorResponse::InternalServerError(err.into()))?;
-
-    Ok(response)
-}
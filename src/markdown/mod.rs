@@ -0,0 +1,468 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Options, Parser, Tag};
+use serde::Deserialize;
+
+use crate::highlight;
+
+#[cfg(test)]
+mod tests;
+
+/// headings deeper than this are not part of the table of contents
+const TOC_MAX_LEVEL: u32 = 4;
+
+/// compiled markdown document
+#[derive(Debug, Default)]
+pub struct Markdown {
+    pub html: String,
+    pub toc: Vec<TocEntry>,
+    /// relative links and images, that are not one of the article files
+    pub unresolved: Vec<String>,
+}
+
+/// the article's media files, relative links are resolved against
+pub struct MediaContext<'a> {
+    pub alias: &'a str,
+    pub files: &'a HashMap<String, PathBuf>,
+}
+
+/// markdown features of the compile pipeline, all enabled by default.
+/// can be switched per article with `markdown: (footnotes: false)` in meta.ron.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MarkdownConfig {
+    pub strikethrough: bool,
+    pub tables: bool,
+    pub footnotes: bool,
+    pub task_lists: bool,
+    pub heading_attributes: bool,
+    /// `> [!NOTE]`, `> [!TIP]` and `> [!WARNING]` blockquotes
+    pub callouts: bool,
+    pub highlight: bool,
+    pub anchors: bool,
+    /// `<picture>` with resized avif/webp variants for article images
+    pub responsive_images: bool,
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            strikethrough: true,
+            tables: true,
+            footnotes: true,
+            task_lists: true,
+            heading_attributes: true,
+            callouts: true,
+            highlight: true,
+            anchors: true,
+            responsive_images: true,
+        }
+    }
+}
+
+impl MarkdownConfig {
+    pub fn options(&self) -> Options {
+        let mut options = Options::empty();
+        options.set(Options::ENABLE_STRIKETHROUGH, self.strikethrough);
+        options.set(Options::ENABLE_TABLES, self.tables);
+        options.set(Options::ENABLE_FOOTNOTES, self.footnotes);
+        options.set(Options::ENABLE_TASKLISTS, self.task_lists);
+        options.set(Options::ENABLE_HEADING_ATTRIBUTES, self.heading_attributes);
+        options
+    }
+}
+
+/// callout kinds, with the title shown above the content
+const CALLOUTS: [(&str, &str); 3] = [("note", "Note"), ("tip", "Tip"), ("warning", "Warning")];
+
+/// runs the markdown pipeline configured for an article
+pub fn compile(raw: &str, config: &MarkdownConfig, media: &MediaContext) -> Markdown {
+    let mut events = Parser::new_ext(raw, config.options()).collect::<Vec<_>>();
+
+    let mut unresolved = Vec::new();
+    events = rewrite_media_links(events, media, &mut unresolved);
+
+    if config.responsive_images {
+        events = responsive_images(events, media);
+    }
+
+    if config.callouts {
+        events = callouts(events);
+    }
+
+    if config.highlight {
+        events = highlight_code_blocks(events.into_iter()).collect();
+    }
+
+    let mut headings = Vec::new();
+    if config.anchors {
+        (events, headings) = anchor_headings(events.into_iter());
+    }
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events.into_iter());
+    Markdown {
+        html,
+        toc: build_toc(headings),
+        unresolved,
+    }
+}
+
+/// rewrites relative links and images like `quad.jpg` or `./cover.jpg` to `/media/{alias}/{file}`,
+/// so they keep working when the alias changes
+pub fn rewrite_media_links<'a>(
+    events: Vec<Event<'a>>,
+    media: &MediaContext,
+    unresolved: &mut Vec<String>,
+) -> Vec<Event<'a>> {
+    events
+        .into_iter()
+        .map(|event| match event {
+            Event::Start(Tag::Link(kind, dest, title)) => Event::Start(Tag::Link(
+                kind,
+                resolve(dest, media, Some(unresolved)),
+                title,
+            )),
+            Event::End(Tag::Link(kind, dest, title)) => {
+                Event::End(Tag::Link(kind, resolve(dest, media, None), title))
+            }
+            Event::Start(Tag::Image(kind, dest, title)) => Event::Start(Tag::Image(
+                kind,
+                resolve(dest, media, Some(unresolved)),
+                title,
+            )),
+            Event::End(Tag::Image(kind, dest, title)) => {
+                Event::End(Tag::Image(kind, resolve(dest, media, None), title))
+            }
+            event => event,
+        })
+        .collect()
+}
+
+/// replaces images of the article's own files with a responsive `<picture>`.
+/// the alt text is flattened, as it is for regular images.
+pub fn responsive_images<'a>(events: Vec<Event<'a>>, media: &MediaContext) -> Vec<Event<'a>> {
+    let prefix = format!("/media/{}/", media.alias);
+    let mut out = Vec::with_capacity(events.len());
+    let mut events = events.into_iter();
+
+    while let Some(event) = events.next() {
+        let Event::Start(Tag::Image(_, dest, title)) = &event else {
+            out.push(event);
+            continue;
+        };
+
+        let Some(path) = dest
+            .strip_prefix(&prefix)
+            .and_then(|file| media.files.get(file))
+        else {
+            out.push(event);
+            continue;
+        };
+
+        let mut inner = Vec::new();
+        let mut depth = 0;
+        for event in events.by_ref() {
+            match &event {
+                Event::Start(Tag::Image(..)) => depth += 1,
+                Event::End(Tag::Image(..)) if depth == 0 => {
+                    inner.push(event);
+                    break;
+                }
+                Event::End(Tag::Image(..)) => depth -= 1,
+                _ => (),
+            }
+            inner.push(event);
+        }
+
+        let alt = inner
+            .iter()
+            .filter_map(|event| match event {
+                Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+                _ => None,
+            })
+            .collect::<String>();
+        let title = (!title.is_empty()).then_some(title.as_ref());
+
+        match crate::images::picture(dest, path, &alt, title) {
+            Some(picture) => out.push(Event::Html(picture.into_string().into())),
+            None => {
+                out.push(event);
+                out.extend(inner);
+            }
+        }
+    }
+
+    out
+}
+
+fn resolve<'a>(
+    dest: CowStr<'a>,
+    media: &MediaContext,
+    unresolved: Option<&mut Vec<String>>,
+) -> CowStr<'a> {
+    let is_relative =
+        !dest.is_empty() && !dest.contains(':') && !dest.starts_with('/') && !dest.starts_with('#');
+    if !is_relative {
+        return dest;
+    }
+
+    let split = dest.find(['?', '#']).unwrap_or(dest.len());
+    let (path, suffix) = dest.split_at(split);
+    let file = path.trim_start_matches("./");
+
+    match media.files.contains_key(file) {
+        true => format!("/media/{}/{}{}", media.alias, file, suffix).into(),
+        false => {
+            if let Some(unresolved) = unresolved {
+                unresolved.push(dest.to_string());
+            }
+            dest
+        }
+    }
+}
+
+/// turns blockquotes starting with `[!NOTE]`, `[!TIP]` or `[!WARNING]` into styled callouts
+pub fn callouts(events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut out = Vec::with_capacity(events.len());
+    let mut quotes: Vec<bool> = Vec::new();
+    let mut events = events.into_iter().peekable();
+
+    while let Some(event) = events.next() {
+        match event {
+            Event::Start(Tag::BlockQuote) => {
+                let Some(Event::Start(Tag::Paragraph)) = events.peek() else {
+                    quotes.push(false);
+                    out.push(Event::Start(Tag::BlockQuote));
+                    continue;
+                };
+
+                // the marker is split into multiple text events by the link parser
+                let mut lookahead = vec![events.next().unwrap()];
+                let mut marker = String::new();
+                while let Some(Event::Text(text)) = events.peek() {
+                    marker.push_str(text);
+                    lookahead.push(events.next().unwrap());
+                }
+
+                let kind = marker
+                    .trim()
+                    .strip_prefix("[!")
+                    .and_then(|marker| marker.strip_suffix(']'))
+                    .and_then(|kind| {
+                        CALLOUTS
+                            .iter()
+                            .find(|(name, _)| kind.eq_ignore_ascii_case(name))
+                    });
+
+                let Some((name, title)) = kind else {
+                    quotes.push(false);
+                    out.push(Event::Start(Tag::BlockQuote));
+                    out.extend(lookahead);
+                    continue;
+                };
+
+                quotes.push(true);
+                out.push(Event::Html(
+                    format!(
+                        "<div class=\"callout callout-{}\">\n<p class=\"callout-title\">{}</p>\n",
+                        name, title
+                    )
+                    .into(),
+                ));
+
+                if let Some(Event::SoftBreak | Event::HardBreak) = events.peek() {
+                    events.next();
+                }
+
+                match events.peek() {
+                    Some(Event::End(Tag::Paragraph)) => _ = events.next(),
+                    _ => out.push(Event::Start(Tag::Paragraph)),
+                }
+            }
+            Event::End(Tag::BlockQuote) => match quotes.pop() {
+                Some(true) => out.push(Event::Html("</div>\n".into())),
+                _ => out.push(Event::End(Tag::BlockQuote)),
+            },
+            event => out.push(event),
+        }
+    }
+
+    out
+}
+
+/// heading in an article's table of contents, nested by level
+#[derive(Debug, Clone)]
+pub struct TocEntry {
+    pub id: String,
+    pub title: String,
+    pub level: u32,
+    pub children: Vec<TocEntry>,
+}
+
+/// heading found while compiling, in document order
+#[derive(Debug)]
+pub struct Heading {
+    pub id: String,
+    pub title: String,
+    pub level: u32,
+}
+
+/// replaces code blocks with server side highlighted html
+pub fn highlight_code_blocks<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+) -> impl Iterator<Item = Event<'a>> {
+    let mut code_block: Option<(String, String)> = None;
+
+    events.filter_map(move |event| match event {
+        Event::Start(Tag::CodeBlock(kind)) => {
+            let lang = match kind {
+                CodeBlockKind::Fenced(lang) => lang.to_string(),
+                CodeBlockKind::Indented => String::new(),
+            };
+            code_block = Some((lang, String::new()));
+            None
+        }
+        Event::Text(text) if code_block.is_some() => {
+            if let Some((_, code)) = code_block.as_mut() {
+                code.push_str(&text);
+            }
+            None
+        }
+        Event::End(Tag::CodeBlock(_)) => code_block
+            .take()
+            .map(|(lang, code)| Event::Html(highlight::highlight(&code, &lang).into())),
+        event => Some(event),
+    })
+}
+
+/// gives every heading a unique slug id and an anchor link to itself
+pub fn anchor_headings<'a>(
+    events: impl Iterator<Item = Event<'a>>,
+) -> (Vec<Event<'a>>, Vec<Heading>) {
+    let events = events.collect::<Vec<_>>();
+    let mut out = Vec::new();
+    let mut headings = Vec::new();
+    let mut heading: Option<Vec<Event<'a>>> = None;
+
+    // explicit `{#id}`s win, generated ids are numbered around them
+    let mut used_ids = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::End(Tag::Heading(_, Some(id), _)) => Some(id.to_string()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading(..)) => heading = Some(Vec::new()),
+            Event::End(Tag::Heading(level, id, classes)) => {
+                let inner = heading.take().unwrap_or_default();
+                let title = inner
+                    .iter()
+                    .filter_map(|event| match event {
+                        Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+                        _ => None,
+                    })
+                    .collect::<String>();
+
+                let id = match id {
+                    Some(id) => id.to_string(),
+                    None => unique_id(slug(&title), &mut used_ids),
+                };
+                let level = level as u32;
+                let class = match classes.is_empty() {
+                    true => String::new(),
+                    false => format!(" class=\"{}\"", escape_attribute(&classes.join(" "))),
+                };
+
+                out.push(Event::Html(
+                    format!("<h{} id=\"{}\"{}>", level, escape_attribute(&id), class).into(),
+                ));
+                out.extend(inner);
+                out.push(Event::Html(
+                    format!(
+                        "<a class=\"heading-anchor\" href=\"#{}\" aria-hidden=\"true\">#</a></h{}>\n",
+                        escape_attribute(&id),
+                        level
+                    )
+                    .into(),
+                ));
+                headings.push(Heading { id, title, level });
+            }
+            event => match heading.as_mut() {
+                Some(inner) => inner.push(event),
+                None => out.push(event),
+            },
+        }
+    }
+
+    (out, headings)
+}
+
+/// nests headings by level. the top level title is left out, it names the article itself.
+pub fn build_toc(headings: Vec<Heading>) -> Vec<TocEntry> {
+    let mut toc = Vec::new();
+    headings
+        .into_iter()
+        .filter(|heading| heading.level > 1 && heading.level <= TOC_MAX_LEVEL)
+        .for_each(|heading| {
+            insert_toc(
+                &mut toc,
+                TocEntry {
+                    id: heading.id,
+                    title: heading.title,
+                    level: heading.level,
+                    children: Vec::new(),
+                },
+            )
+        });
+    toc
+}
+
+fn insert_toc(list: &mut Vec<TocEntry>, entry: TocEntry) {
+    match list.last_mut() {
+        Some(last) if entry.level > last.level => insert_toc(&mut last.children, entry),
+        _ => list.push(entry),
+    }
+}
+
+/// url friendly id from heading text, `Boids & Quadtrees` becomes `boids-quadtrees`
+fn slug(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_matches('-');
+    match slug.is_empty() {
+        true => "section".into(),
+        false => slug.into(),
+    }
+}
+
+/// the id itself or, if taken, the id with the first free number appended
+fn unique_id(id: String, used_ids: &mut HashSet<String>) -> String {
+    let mut unique = id.clone();
+    let mut n = 0;
+    while used_ids.contains(&unique) {
+        n += 1;
+        unique = format!("{}-{}", id, n);
+    }
+    used_ids.insert(unique.clone());
+    unique
+}
+
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::new();
+    _ = pulldown_cmark::escape::escape_html(&mut escaped, value);
+    escaped
+}
@@ -0,0 +1,122 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use super::{compile, Markdown, MarkdownConfig, MediaContext};
+
+/// compiles with the fixture article, owning `quad.jpg` and `cover.jpg`
+fn compile_fixture(source: &str, config: &MarkdownConfig) -> Markdown {
+    let files = ["quad.jpg", "cover.jpg"]
+        .into_iter()
+        .map(|file| (file.to_string(), PathBuf::from(file)))
+        .collect::<HashMap<_, _>>();
+
+    let media = MediaContext {
+        alias: "fixture",
+        files: &files,
+    };
+    compile(source, config, &media)
+}
+
+/// compiles `fixtures/{name}.md` and compares it with `fixtures/{name}.html`.
+/// run with `UPDATE_SNAPSHOTS=1` to write the current output as the new snapshot.
+fn assert_snapshot(name: &str) {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/markdown/fixtures");
+    let source = std::fs::read_to_string(dir.join(format!("{}.md", name))).unwrap();
+    let html = compile_fixture(&source, &MarkdownConfig::default()).html;
+
+    let snapshot = dir.join(format!("{}.html", name));
+    if std::env::var("UPDATE_SNAPSHOTS").is_ok() {
+        std::fs::write(&snapshot, &html).unwrap();
+    }
+
+    let expected = std::fs::read_to_string(&snapshot).unwrap_or_default();
+    assert_eq!(html, expected, "snapshot '{}' does not match", name);
+}
+
+#[test]
+fn tables() {
+    assert_snapshot("tables");
+}
+
+#[test]
+fn footnotes() {
+    assert_snapshot("footnotes");
+}
+
+#[test]
+fn task_lists() {
+    assert_snapshot("task_lists");
+}
+
+#[test]
+fn callouts() {
+    assert_snapshot("callouts");
+}
+
+#[test]
+fn headings() {
+    assert_snapshot("headings");
+}
+
+#[test]
+fn code() {
+    assert_snapshot("code");
+}
+
+#[test]
+fn media_links() {
+    assert_snapshot("media_links");
+}
+
+#[test]
+fn unresolved_media_links_are_reported() {
+    let source = include_str!("fixtures/media_links.md");
+    let markdown = compile_fixture(source, &MarkdownConfig::default());
+    assert_eq!(markdown.unresolved, ["missing.png", "../other/file.jpg"]);
+}
+
+#[test]
+fn toc_is_nested_by_level() {
+    let source = include_str!("fixtures/headings.md");
+    let toc = compile_fixture(source, &MarkdownConfig::default()).toc;
+
+    let ids = toc
+        .iter()
+        .map(|entry| entry.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, ["setup", "setup-1", "custom-id"]);
+    assert_eq!(toc[0].children[0].id, "lets-write-some-lua");
+    assert_eq!(toc[0].children[0].title, "Let's write some Lua");
+}
+
+#[test]
+fn features_can_be_disabled() {
+    let config = MarkdownConfig {
+        tables: false,
+        callouts: false,
+        ..Default::default()
+    };
+
+    let tables = compile_fixture(include_str!("fixtures/tables.md"), &config).html;
+    assert!(!tables.contains("<table>"));
+
+    let callouts = compile_fixture(include_str!("fixtures/callouts.md"), &config).html;
+    assert!(!callouts.contains("callout"));
+    assert!(callouts.contains("<blockquote>"));
+}
+
+#[test]
+fn explicit_heading_ids_are_reserved_and_escaped() {
+    let source = "## Setup\n\n## Later {#setup}\n\n## Quoted {#a&b .x\"y}\n";
+    let markdown = compile_fixture(source, &MarkdownConfig::default());
+
+    let ids = markdown
+        .toc
+        .iter()
+        .map(|entry| entry.id.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, ["setup-1", "setup", "a&b"]);
+    assert!(markdown
+        .html
+        .contains(r#"<h2 id="a&amp;b" class="x&quot;y">Quoted"#));
+    assert!(markdown.html.contains(r##"href="#a&amp;b""##));
+}
@@ -0,0 +1,355 @@
+use std::{
+    io::SeekFrom,
+    ops::Range,
+    path::{Path as FsPath, PathBuf},
+    time::SystemTime,
+};
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::{
+    cache::{is_fresh, write_atomic},
+    compression::{self, Encoding, COMPRESSED},
+    images, AppState, ErrorResponse, PreviewQuery,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// article media may be replaced, clients revalidate with the etag after an hour
+const CACHE_CONTROL: &str = "public, max-age=3600";
+
+pub async fn serve_article_media(
+    Path((alias, file)): Path<(String, String)>,
+    Query(variant): Query<images::VariantQuery>,
+    Query(query): Query<PreviewQuery>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Response, ErrorResponse> {
+    let preview =
+        state.is_preview(query.preview.as_deref()) || state.is_preview(referrer_preview(&headers));
+
+    // `game.wasm` is also served from a stored `game.wasm.gz`
+    let mut file_path = state
+        .articles
+        .read()
+        .await
+        .find_by_alias(&alias)
+        .filter(|article| article.meta.is_public() || preview)
+        .and_then(|article| {
+            article.files.get(&file).cloned().or_else(|| {
+                COMPRESSED.into_iter().find_map(|encoding| {
+                    let name = format!("{}.{}", file, encoding.extension());
+                    article.files.get(&name).cloned()
+                })
+            })
+        })
+        .ok_or_else(|| ErrorResponse::FileNotFound)?;
+
+    if !variant.is_valid() {
+        return Err(ErrorResponse::FileNotFound);
+    }
+
+    let mut mime_type = mime_guess::from_path(content_path(&file_path).0)
+        .first_or_octet_stream()
+        .to_string();
+
+    if !variant.is_original() && images::is_resizable(&file_path) {
+        mime_type = images::mime(&file_path, variant.format);
+        file_path = images::variant(&alias, &file_path, &variant)
+            .await
+            .map_err(|err| {
+                tracing::error!("image variant {}/{}: {}", alias, file, err);
+                ErrorResponse::InternalServerError(err.into())
+            })?;
+    }
+
+    let (file_path, encoding) = encoded(&cache_dir(), &alias, &file_path, &mime_type, &headers)
+        .await
+        .map_err(|err| ErrorResponse::InternalServerError(err.into()))?;
+
+    serve_file(&file_path, &mime_type, encoding, &headers).await
+}
+
+/// the preview token of the page embedding the media,
+/// images and videos of a previewed article are requested without it
+fn referrer_preview(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::REFERER)?
+        .to_str()
+        .ok()?
+        .split_once('?')?
+        .1
+        .split(['&', '#'])
+        .find_map(|param| param.strip_prefix("preview="))
+}
+
+fn cache_dir() -> PathBuf {
+    std::env::var("COMPRESSION_CACHE")
+        .unwrap_or("compression_cache".to_string())
+        .into()
+}
+
+/// strips the encoding extension of files stored compressed, `game.wasm.gz` is `game.wasm`
+fn content_path(path: &FsPath) -> (PathBuf, Encoding) {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(Encoding::from_extension)
+    {
+        Some(encoding) => (path.with_extension(""), encoding),
+        None => (path.to_path_buf(), Encoding::Identity),
+    }
+}
+
+/// picks the file to send for the client's `Accept-Encoding`.
+/// sibling `.br`, `.zst` and `.gz` files are used as they are, other encodings
+/// are compressed on first request and cached in `cache`.
+async fn encoded(
+    cache: &FsPath,
+    alias: &str,
+    path: &FsPath,
+    mime: &str,
+    headers: &HeaderMap,
+) -> std::io::Result<(PathBuf, Encoding)> {
+    let (content, stored) = content_path(path);
+    let name = content
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+
+    if stored != Encoding::Identity {
+        if compression::accepts(headers, stored) {
+            return Ok((path.to_path_buf(), stored));
+        }
+
+        let decoded = cache.join(alias).join(name);
+        if !is_fresh(&decoded, path).await {
+            let bytes = tokio::fs::read(path).await?;
+            let target = decoded.clone();
+            tokio::task::spawn_blocking(move || {
+                write_atomic(&target, &compression::decompress(&bytes, stored)?)
+            })
+            .await??;
+        }
+        return Ok((decoded, Encoding::Identity));
+    }
+
+    if !compression::is_compressible(mime) {
+        return Ok((path.to_path_buf(), Encoding::Identity));
+    }
+
+    let encoding = compression::negotiate(headers, &COMPRESSED);
+    if encoding == Encoding::Identity {
+        return Ok((path.to_path_buf(), encoding));
+    }
+
+    let sibling = path.with_file_name(format!("{}.{}", name, encoding.extension()));
+    if is_fresh(&sibling, path).await {
+        return Ok((sibling, encoding));
+    }
+
+    let cached = cache
+        .join(alias)
+        .join(format!("{}.{}", name, encoding.extension()));
+    if !is_fresh(&cached, path).await {
+        let bytes = tokio::fs::read(path).await?;
+        let target = cached.clone();
+        tokio::task::spawn_blocking(move || {
+            write_atomic(&target, &compression::compress(&bytes, encoding)?)
+        })
+        .await??;
+    }
+    Ok((cached, encoding))
+}
+
+/// validators of a file, derived from its size and modification time
+#[derive(Debug, Clone)]
+pub struct Validators {
+    pub etag: String,
+    pub last_modified: DateTime<Utc>,
+}
+
+impl Validators {
+    pub fn new(len: u64, modified: SystemTime) -> Self {
+        let last_modified = DateTime::<Utc>::from(modified);
+        Self {
+            etag: format!("\"{:x}-{:x}\"", len, last_modified.timestamp()),
+            last_modified,
+        }
+    }
+
+    fn http_date(&self) -> String {
+        http_date(&self.last_modified)
+    }
+}
+
+/// what to answer to a request, after evaluating its conditional and range headers
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    NotModified,
+    Full,
+    Partial(Range<u64>),
+    Unsatisfiable,
+}
+
+/// evaluates `If-None-Match`, `If-Modified-Since`, `If-Range` and `Range` against a file
+pub fn evaluate(headers: &HeaderMap, validators: &Validators, len: u64) -> Outcome {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+    // if-none-match takes precedence, if-modified-since is only checked without it
+    let not_modified = match header(header::IF_NONE_MATCH) {
+        Some(tags) => etag_matches(tags, &validators.etag),
+        None => header(header::IF_MODIFIED_SINCE)
+            .and_then(parse_http_date)
+            .map(|since| validators.last_modified.timestamp() <= since.timestamp())
+            .unwrap_or(false),
+    };
+    if not_modified {
+        return Outcome::NotModified;
+    }
+
+    let Some(range) = header(header::RANGE) else {
+        return Outcome::Full;
+    };
+
+    // a stale if-range sends the whole file instead of a part of the new one
+    let range_allowed = match header(header::IF_RANGE) {
+        Some(condition) if condition.starts_with('"') => condition == validators.etag,
+        Some(condition) => parse_http_date(condition)
+            .map(|date| date.timestamp() == validators.last_modified.timestamp())
+            .unwrap_or(false),
+        None => true,
+    };
+    if !range_allowed {
+        return Outcome::Full;
+    }
+
+    match parse_range(range, len) {
+        Some(Ok(range)) => Outcome::Partial(range),
+        Some(Err(())) => Outcome::Unsatisfiable,
+        None => Outcome::Full,
+    }
+}
+
+/// streams a file with caching headers, answering conditional and range requests
+pub async fn serve_file(
+    path: &FsPath,
+    mime: &str,
+    encoding: Encoding,
+    headers: &HeaderMap,
+) -> Result<Response, ErrorResponse> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|_| ErrorResponse::FileNotFound)?;
+    let metadata = file
+        .metadata()
+        .await
+        .map_err(|err| ErrorResponse::InternalServerError(err.into()))?;
+
+    let len = metadata.len();
+    let validators = Validators::new(len, metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+
+    let mut response = Response::builder()
+        .header(header::ETAG, &validators.etag)
+        .header(header::LAST_MODIFIED, validators.http_date())
+        .header(header::CACHE_CONTROL, CACHE_CONTROL)
+        .header(header::ACCEPT_RANGES, "bytes");
+
+    if compression::is_compressible(mime) {
+        response = response.header(header::VARY, "accept-encoding");
+    }
+    if encoding != Encoding::Identity {
+        response = response.header(header::CONTENT_ENCODING, encoding.token());
+    }
+
+    let response = match evaluate(headers, &validators, len) {
+        Outcome::NotModified => response
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty()),
+        Outcome::Unsatisfiable => response
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+            .body(Body::empty()),
+        Outcome::Full => response
+            .header(header::CONTENT_TYPE, mime)
+            .header(header::CONTENT_LENGTH, len)
+            .body(Body::from_stream(tokio_util::io::ReaderStream::new(file))),
+        Outcome::Partial(range) => {
+            file.seek(SeekFrom::Start(range.start))
+                .await
+                .map_err(|err| ErrorResponse::InternalServerError(err.into()))?;
+            let part = file.take(range.end - range.start);
+
+            response
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_TYPE, mime)
+                .header(header::CONTENT_LENGTH, range.end - range.start)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", range.start, range.end - 1, len),
+                )
+                .body(Body::from_stream(tokio_util::io::ReaderStream::new(part)))
+        }
+    };
+
+    response.map_err(|err| ErrorResponse::InternalServerError(err.into()))
+}
+
+/// weak comparison, as required for `If-None-Match`
+fn etag_matches(tags: &str, etag: &str) -> bool {
+    tags.split(',')
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// parses a single `bytes=` range into a half open range.
+/// `None` ignores the header, multiple ranges are answered with the full file.
+fn parse_range(range: &str, len: u64) -> Option<Result<Range<u64>, ()>> {
+    let spec = range.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = match (start.is_empty(), end.is_empty()) {
+        // suffix range, the last n bytes
+        (true, false) => {
+            let suffix = end.parse::<u64>().ok()?;
+            len.saturating_sub(suffix)..len
+        }
+        (false, true) => start.parse::<u64>().ok()?..len,
+        (false, false) => {
+            let (start, end) = (start.parse::<u64>().ok()?, end.parse::<u64>().ok()?);
+            if end < start {
+                return None;
+            }
+            start..end.saturating_add(1).min(len)
+        }
+        (true, true) => return None,
+    };
+
+    match range.start < range.end {
+        true => Some(Ok(range)),
+        false => Some(Err(())),
+    }
+}
+
+pub fn http_date(date: &DateTime<Utc>) -> String {
+    date.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn parse_http_date(date: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(date)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
@@ -0,0 +1,279 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use axum::{
+    body::to_bytes,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+};
+
+use crate::compression::{compress, Encoding};
+
+use super::{
+    encoded, evaluate, http_date, parse_range, referrer_preview, serve_file, Outcome, Validators,
+};
+
+const CONTENT: &[u8] = b"0123456789abcdefghij";
+
+fn validators() -> Validators {
+    Validators::new(
+        20,
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+    )
+}
+
+fn headers(list: &[(header::HeaderName, &str)]) -> HeaderMap {
+    list.iter()
+        .map(|(name, value)| (name.clone(), HeaderValue::from_str(value).unwrap()))
+        .collect()
+}
+
+/// writes the test content to a unique temp file
+fn media_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("media-test-{}-{}", std::process::id(), name));
+    std::fs::write(&path, CONTENT).unwrap();
+    path
+}
+
+async fn serve(name: &str, list: &[(header::HeaderName, &str)]) -> Response {
+    let path = media_file(name);
+    let response = match serve_file(
+        &path,
+        "application/wasm",
+        Encoding::Identity,
+        &headers(list),
+    )
+    .await
+    {
+        Ok(response) => response,
+        Err(_) => panic!("failed to serve {:?}", path),
+    };
+    std::fs::remove_file(path).unwrap();
+    response
+}
+
+async fn body(response: Response) -> Vec<u8> {
+    to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .to_vec()
+}
+
+#[test]
+fn if_none_match() {
+    let validators = validators();
+    let matching = headers(&[(header::IF_NONE_MATCH, &validators.etag)]);
+    assert_eq!(evaluate(&matching, &validators, 20), Outcome::NotModified);
+
+    let weak = format!("\"other\", W/{}", validators.etag);
+    let weak = headers(&[(header::IF_NONE_MATCH, &weak)]);
+    assert_eq!(evaluate(&weak, &validators, 20), Outcome::NotModified);
+
+    let any = headers(&[(header::IF_NONE_MATCH, "*")]);
+    assert_eq!(evaluate(&any, &validators, 20), Outcome::NotModified);
+
+    let stale = headers(&[(header::IF_NONE_MATCH, "\"stale\"")]);
+    assert_eq!(evaluate(&stale, &validators, 20), Outcome::Full);
+}
+
+#[test]
+fn if_modified_since() {
+    let validators = validators();
+    let same = http_date(&validators.last_modified);
+    let same = headers(&[(header::IF_MODIFIED_SINCE, &same)]);
+    assert_eq!(evaluate(&same, &validators, 20), Outcome::NotModified);
+
+    let older = http_date(&(validators.last_modified - chrono::Duration::days(1)));
+    let older = headers(&[(header::IF_MODIFIED_SINCE, &older)]);
+    assert_eq!(evaluate(&older, &validators, 20), Outcome::Full);
+
+    let invalid = headers(&[(header::IF_MODIFIED_SINCE, "yesterday")]);
+    assert_eq!(evaluate(&invalid, &validators, 20), Outcome::Full);
+}
+
+#[test]
+fn if_none_match_takes_precedence() {
+    let validators = validators();
+    let list = headers(&[
+        (header::IF_NONE_MATCH, "\"stale\""),
+        (
+            header::IF_MODIFIED_SINCE,
+            &http_date(&validators.last_modified),
+        ),
+    ]);
+    assert_eq!(evaluate(&list, &validators, 20), Outcome::Full);
+}
+
+#[test]
+fn ranges() {
+    let validators = validators();
+    let range = |value: &str| evaluate(&headers(&[(header::RANGE, value)]), &validators, 20);
+
+    assert_eq!(range("bytes=0-4"), Outcome::Partial(0..5));
+    assert_eq!(range("bytes=15-"), Outcome::Partial(15..20));
+    assert_eq!(range("bytes=-5"), Outcome::Partial(15..20));
+    assert_eq!(range("bytes=10-100"), Outcome::Partial(10..20));
+    assert_eq!(range("bytes=20-"), Outcome::Unsatisfiable);
+    assert_eq!(range("bytes=0-1,5-6"), Outcome::Full);
+    assert_eq!(range("lines=0-1"), Outcome::Full);
+}
+
+#[test]
+fn range_end_at_u64_max_is_clamped() {
+    assert_eq!(
+        parse_range("bytes=0-18446744073709551615", 20),
+        Some(Ok(0..20))
+    );
+    assert_eq!(
+        parse_range(&format!("bytes={}-{}", u64::MAX, u64::MAX), 20),
+        Some(Err(()))
+    );
+}
+
+#[test]
+fn stale_if_range_sends_full_file() {
+    let validators = validators();
+    let fresh = headers(&[
+        (header::RANGE, "bytes=0-4"),
+        (header::IF_RANGE, &validators.etag),
+    ]);
+    assert_eq!(evaluate(&fresh, &validators, 20), Outcome::Partial(0..5));
+
+    let stale = headers(&[
+        (header::RANGE, "bytes=0-4"),
+        (header::IF_RANGE, "\"stale\""),
+    ]);
+    assert_eq!(evaluate(&stale, &validators, 20), Outcome::Full);
+}
+
+#[tokio::test]
+async fn serves_full_file_with_validators() {
+    let response = serve("full", &[]).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key(header::ETAG));
+    assert!(response.headers().contains_key(header::LAST_MODIFIED));
+    assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "20");
+    assert_eq!(body(response).await, CONTENT);
+}
+
+#[tokio::test]
+async fn serves_not_modified() {
+    let path = media_file("etag");
+    let first = serve_file(&path, "video/webm", Encoding::Identity, &HeaderMap::new()).await;
+    let etag = first.ok().unwrap().headers()[header::ETAG].clone();
+
+    let conditional = headers(&[(header::IF_NONE_MATCH, etag.to_str().unwrap())]);
+    let response = serve_file(&path, "video/webm", Encoding::Identity, &conditional).await;
+    std::fs::remove_file(path).unwrap();
+
+    let response = response.ok().unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert!(body(response).await.is_empty());
+}
+
+#[tokio::test]
+async fn serves_partial_content() {
+    let response = serve("partial", &[(header::RANGE, "bytes=5-9")]).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 5-9/20");
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "5");
+    assert_eq!(body(response).await, b"56789");
+
+    let response = serve("unsatisfiable", &[(header::RANGE, "bytes=30-")]).await;
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */20");
+}
+
+#[test]
+fn preview_token_is_taken_from_the_referrer() {
+    let referrer = |value: &str| headers(&[(header::REFERER, value)]);
+
+    assert_eq!(
+        referrer_preview(&referrer("https://lommix.de/article/draft?preview=secret")),
+        Some("secret")
+    );
+    assert_eq!(
+        referrer_preview(&referrer(
+            "https://lommix.de/article/draft?a=1&preview=secret#top"
+        )),
+        Some("secret")
+    );
+    assert_eq!(
+        referrer_preview(&referrer("https://lommix.de/article/draft")),
+        None
+    );
+    assert_eq!(referrer_preview(&HeaderMap::new()), None);
+}
+
+/// an empty temp dir with an article dir and a compression cache
+fn encoding_dirs(name: &str) -> (PathBuf, PathBuf) {
+    let root = std::env::temp_dir().join(format!("media-encoding-{}-{}", std::process::id(), name));
+    _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("article")).unwrap();
+    (root.join("article"), root.join("cache"))
+}
+
+fn accept(value: &str) -> HeaderMap {
+    headers(&[(header::ACCEPT_ENCODING, value)])
+}
+
+#[tokio::test]
+async fn sibling_compressed_files_are_reused() {
+    let (dir, cache) = encoding_dirs("sibling");
+    let script = dir.join("game.js");
+    std::fs::write(&script, "let a = 1;").unwrap();
+    std::fs::write(dir.join("game.js.br"), "stored brotli").unwrap();
+
+    let (path, encoding) = encoded(&cache, "a", &script, "text/javascript", &accept("br"))
+        .await
+        .unwrap();
+    assert_eq!((path, encoding), (dir.join("game.js.br"), Encoding::Brotli));
+
+    // without a sibling, the file is compressed into the cache
+    let (path, encoding) = encoded(&cache, "a", &script, "text/javascript", &accept("gzip"))
+        .await
+        .unwrap();
+    assert_eq!(
+        (&path, encoding),
+        (&cache.join("a/game.js.gz"), Encoding::Gzip)
+    );
+    assert!(path.exists());
+
+    // images are never compressed again
+    let image = dir.join("cover.png");
+    std::fs::write(&image, "png").unwrap();
+    let (path, encoding) = encoded(&cache, "a", &image, "image/png", &accept("br"))
+        .await
+        .unwrap();
+    assert_eq!((path, encoding), (image, Encoding::Identity));
+
+    std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn stored_gzip_is_decoded_for_clients_without_gzip() {
+    let (dir, cache) = encoding_dirs("stored");
+    let wasm = dir.join("game.wasm.gz");
+    std::fs::write(&wasm, compress(CONTENT, Encoding::Gzip).unwrap()).unwrap();
+
+    let (path, encoding) = encoded(&cache, "a", &wasm, "application/wasm", &accept("gzip"))
+        .await
+        .unwrap();
+    assert_eq!((path, encoding), (wasm.clone(), Encoding::Gzip));
+
+    for headers in [accept("br"), HeaderMap::new()] {
+        let (path, encoding) = encoded(&cache, "a", &wasm, "application/wasm", &headers)
+            .await
+            .unwrap();
+        assert_eq!(
+            (&path, encoding),
+            (&cache.join("a/game.wasm"), Encoding::Identity)
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), CONTENT);
+    }
+
+    std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+}
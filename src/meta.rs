@@ -0,0 +1,176 @@
+use std::sync::OnceLock;
+
+use chrono::NaiveDate;
+use maud::{html, Markup, PreEscaped};
+use serde_json::json;
+
+use crate::{files::Article, og_image, sitemap};
+
+pub const SITE_NAME: &str = "Lommix's Blog";
+const AUTHOR: &str = "Lommix";
+const TWITTER: &str = "@Lommix1";
+
+/// public url of the site without a trailing slash, set with `BASE_URL`
+pub fn base_url() -> &'static str {
+    static BASE_URL: OnceLock<String> = OnceLock::new();
+    BASE_URL.get_or_init(|| {
+        std::env::var("BASE_URL")
+            .unwrap_or("https://lommix.com".into())
+            .trim_end_matches('/')
+            .to_string()
+    })
+}
+
+/// resolves a site path like `media/x/cover.jpg` or `/blog` against the base url
+pub fn absolute_url(path: &str) -> String {
+    match path.starts_with("http://") || path.starts_with("https://") {
+        true => path.to_string(),
+        false => format!("{}/{}", base_url(), path.trim_start_matches('/')),
+    }
+}
+
+/// metadata of a rendered page, for search engines and link previews
+pub struct PageMeta {
+    pub title: String,
+    pub description: String,
+    pub keywords: String,
+    pub image: Option<String>,
+    /// site path of the page, the canonical url
+    pub path: String,
+    pub article: Option<ArticleInfo>,
+}
+
+/// the parts of an article, that only `og:type=article` pages have
+pub struct ArticleInfo {
+    pub published: NaiveDate,
+    pub modified: NaiveDate,
+    pub tags: Vec<String>,
+}
+
+impl PageMeta {
+    pub fn site(path: &str) -> Self {
+        PageMeta {
+            title: SITE_NAME.into(),
+            description: "Explore the intersection of game development and web development on my blog, featuring in-depth discussions and tutorials on cutting-edge technologies like Rust and Go. Enhance your coding experience with tips and tricks for Neovim, Ai, Game and Webdev.".into(),
+            keywords: "Webdev, Rust, Gamedev, Blog".into(),
+            image: Some(og_image::SITE_URL.into()),
+            path: path.into(),
+            article: None,
+        }
+    }
+
+    pub fn canonical_url(&self) -> String {
+        absolute_url(&self.path)
+    }
+
+    /// title, description, open graph, twitter card, canonical link and json-ld
+    pub fn render(&self) -> Markup {
+        let canonical = self.canonical_url();
+        let image = self.image.as_deref().map(absolute_url);
+        let card = match image {
+            Some(_) => "summary_large_image",
+            None => "summary",
+        };
+
+        html!(
+            title { (self.title) }
+            link rel="canonical" href=(canonical);
+
+            meta name="title" content=(self.title);
+            meta name="description" content=(self.description);
+            meta name="keywords" content=(self.keywords);
+
+            meta property="og:site_name" content=(SITE_NAME);
+            meta property="og:locale" content="en_US";
+            meta property="og:url" content=(canonical);
+            meta property="og:title" content=(self.title);
+            meta property="og:description" content=(self.description);
+            @if let Some(image) = &image {
+                meta property="og:image" content=(image);
+                meta property="og:image:width" content=(og_image::WIDTH);
+                meta property="og:image:height" content=(og_image::HEIGHT);
+                meta property="og:image:alt" content=(self.title);
+            }
+
+            @match &self.article {
+                Some(article) => {
+                    meta property="og:type" content="article";
+                    meta property="article:published_time" content=(iso_date(article.published));
+                    meta property="article:modified_time" content=(iso_date(article.modified));
+                    meta property="article:author" content=(AUTHOR);
+                    @for tag in &article.tags {
+                        meta property="article:tag" content=(tag);
+                    }
+                },
+                None => {
+                    meta property="og:type" content="website";
+                }
+            }
+
+            meta name="twitter:card" content=(card);
+            meta name="twitter:site" content=(TWITTER);
+            meta name="twitter:creator" content=(TWITTER);
+            meta name="twitter:title" content=(self.title);
+            meta name="twitter:description" content=(self.description);
+            @if let Some(image) = &image {
+                meta name="twitter:image" content=(image);
+            }
+
+            script type="application/ld+json" { (self.json_ld()) }
+        )
+    }
+
+    /// schema.org `BlogPosting` for articles, `WebSite` for everything else.
+    /// `</` is escaped, so the json can not close the script tag.
+    fn json_ld(&self) -> PreEscaped<String> {
+        let author = json!({ "@type": "Person", "name": AUTHOR, "url": absolute_url("/about") });
+        let data = match &self.article {
+            Some(article) => json!({
+                "@context": "https://schema.org",
+                "@type": "BlogPosting",
+                "headline": self.title,
+                "description": self.description,
+                "image": self.image.as_deref().map(absolute_url),
+                "datePublished": iso_date(article.published),
+                "dateModified": iso_date(article.modified),
+                "keywords": article.tags,
+                "url": self.canonical_url(),
+                "mainEntityOfPage": { "@type": "WebPage", "@id": self.canonical_url() },
+                "author": author,
+                "publisher": author,
+            }),
+            None => json!({
+                "@context": "https://schema.org",
+                "@type": "WebSite",
+                "name": SITE_NAME,
+                "description": self.description,
+                "url": absolute_url("/"),
+                "author": author,
+            }),
+        };
+
+        PreEscaped(data.to_string().replace("</", "<\\/"))
+    }
+}
+
+impl From<&Article> for PageMeta {
+    fn from(article: &Article) -> Self {
+        let meta = &article.meta;
+        PageMeta {
+            title: meta.title.clone(),
+            description: meta.teaser.clone(),
+            keywords: meta.tag_set.iter().cloned().collect::<Vec<_>>().join(", "),
+            image: Some(og_image::article_url(&meta.alias)),
+            path: format!("/article/{}", meta.alias),
+            article: Some(ArticleInfo {
+                published: meta.published_at,
+                modified: sitemap::last_modified(article),
+                tags: meta.tag_set.iter().cloned().collect(),
+            }),
+        }
+    }
+}
+
+fn iso_date(date: NaiveDate) -> String {
+    format!("{}T00:00:00Z", date.format("%Y-%m-%d"))
+}
@@ -0,0 +1,305 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Cursor,
+    path::{Path as FsPath, PathBuf},
+    sync::OnceLock,
+};
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont, VariableFont};
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Response,
+};
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, Rgba, RgbaImage};
+
+use crate::{
+    cache::{is_fresh, write_atomic},
+    compression::Encoding,
+    files::Article,
+    media::serve_file,
+    meta::SITE_NAME,
+    AppState, ErrorResponse,
+};
+
+pub const WIDTH: u32 = 1200;
+pub const HEIGHT: u32 = 630;
+
+const PADDING: i32 = 60;
+const BACKGROUND: Rgba<u8> = Rgba([2, 6, 23, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const LINK: Rgba<u8> = Rgba([194, 194, 242, 255]);
+const TAGLINE: &str = "Gamedev, web wizardry & educational content";
+
+/// part of every cache path, bump it when the card layout changes
+const CARD_VERSION: u32 = 1;
+
+const FONT: &[u8] = include_bytes!("../../static/fonts/OpenSans-VariableFont_wdth,wght.ttf");
+
+/// the variable font instanced at a weight
+fn font(weight: f32) -> FontRef<'static> {
+    let mut font = FontRef::try_from_slice(FONT).expect("invalid embedded font");
+    font.set_variation(b"wght", weight);
+    font
+}
+
+fn regular() -> &'static FontRef<'static> {
+    static FONT: OnceLock<FontRef<'static>> = OnceLock::new();
+    FONT.get_or_init(|| font(400.0))
+}
+
+fn bold() -> &'static FontRef<'static> {
+    static FONT: OnceLock<FontRef<'static>> = OnceLock::new();
+    FONT.get_or_init(|| font(700.0))
+}
+
+/// the url of an article's share card
+pub fn article_url(alias: &str) -> String {
+    format!("/og/{}", alias)
+}
+
+/// the share card of pages, that are not an article
+pub const SITE_URL: &str = "/og";
+
+/// share card of an article, rendered on first request and cached until the article changes
+pub async fn article(
+    Path(alias): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ErrorResponse> {
+    let article = state
+        .articles
+        .read()
+        .await
+        .find_by_alias(&alias)
+        .filter(|article| article.meta.is_public())
+        .cloned()
+        .ok_or(ErrorResponse::FileNotFound)?;
+
+    let cached = cache_path(&alias);
+    let sources = [Some(article.dir.join("meta.ron")), cover(&article)];
+    let mut fresh = true;
+    for source in sources.iter().flatten() {
+        fresh &= is_fresh(&cached, source).await;
+    }
+
+    if !fresh {
+        let target = cached.clone();
+        tokio::task::spawn_blocking(move || {
+            let tags = article
+                .meta
+                .tag_set
+                .iter()
+                .map(|tag| format!("#{}", tag))
+                .collect::<Vec<_>>()
+                .join("  ");
+            let card = render(&article.meta.title, &tags, cover(&article).as_deref());
+            write_jpeg(&target, &card)
+        })
+        .await
+        .map_err(|err| ErrorResponse::InternalServerError(err.into()))?
+        .map_err(|err| ErrorResponse::InternalServerError(err.into()))?;
+    }
+
+    serve_file(&cached, "image/jpeg", Encoding::Identity, &headers).await
+}
+
+/// share card of the site itself. it has no source file to compare with,
+/// so its cache name changes with the text on it.
+pub async fn site(headers: HeaderMap) -> Result<Response, ErrorResponse> {
+    let cached = cache_path(&format!("_site-{}", site_fingerprint()));
+    if !tokio::fs::try_exists(&cached).await.unwrap_or(false) {
+        let target = cached.clone();
+        tokio::task::spawn_blocking(move || write_jpeg(&target, &render(SITE_NAME, TAGLINE, None)))
+            .await
+            .map_err(|err| ErrorResponse::InternalServerError(err.into()))?
+            .map_err(|err| ErrorResponse::InternalServerError(err.into()))?;
+    }
+
+    serve_file(&cached, "image/jpeg", Encoding::Identity, &headers).await
+}
+
+fn cache_path(name: &str) -> PathBuf {
+    std::env::var("IMAGE_CACHE")
+        .map(PathBuf::from)
+        .unwrap_or("image_cache".into())
+        .join(format!("og-v{}", CARD_VERSION))
+        .join(format!("{}.jpg", name))
+}
+
+fn site_fingerprint() -> String {
+    let mut hasher = DefaultHasher::new();
+    (SITE_NAME, TAGLINE).hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// the cover file, if it is one of the article files
+fn cover(article: &Article) -> Option<PathBuf> {
+    let file_name = article.meta.cover.rsplit('/').next()?;
+    article.files.get(file_name).cloned()
+}
+
+fn write_jpeg(path: &FsPath, card: &RgbaImage) -> anyhow::Result<()> {
+    let mut bytes = Cursor::new(Vec::new());
+    DynamicImage::ImageRgba8(card.clone())
+        .to_rgb8()
+        .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, 82))?;
+    write_atomic(path, &bytes.into_inner())?;
+    Ok(())
+}
+
+/// renders a 1200x630 card: the cropped cover darkened towards the bottom,
+/// the site name on top and title and subtitle at the bottom.
+pub fn render(title: &str, subtitle: &str, cover: Option<&FsPath>) -> RgbaImage {
+    let mut card = RgbaImage::from_pixel(WIDTH, HEIGHT, BACKGROUND);
+
+    if let Some(image) = cover.and_then(|cover| image::open(cover).ok()) {
+        card = image
+            .resize_to_fill(WIDTH, HEIGHT, FilterType::Lanczos3)
+            .to_rgba8();
+
+        // the text stays readable on bright covers
+        for (_, y, pixel) in card.enumerate_pixels_mut() {
+            let shade = 0.45 + 0.45 * (y as f32 / HEIGHT as f32);
+            blend(pixel, BACKGROUND, shade);
+        }
+    }
+
+    let accent = RgbaImage::from_pixel(8, 44, LINK);
+    image::imageops::overlay(&mut card, &accent, PADDING as i64, PADDING as i64);
+    draw_text(
+        &mut card,
+        bold(),
+        36.0,
+        WHITE,
+        PADDING + 24,
+        PADDING + 36,
+        SITE_NAME,
+    );
+
+    let max_width = WIDTH as f32 - 2.0 * PADDING as f32;
+    let subtitle_y = HEIGHT as i32 - PADDING;
+    let lines = wrap(bold(), 64.0, title, max_width, 3);
+    let line_height = 76;
+    let title_y = subtitle_y - 64 - line_height * (lines.len() as i32 - 1);
+
+    for (i, line) in lines.iter().enumerate() {
+        let y = title_y + line_height * i as i32;
+        draw_text(&mut card, bold(), 64.0, WHITE, PADDING, y, line);
+    }
+    draw_text(
+        &mut card,
+        regular(),
+        32.0,
+        LINK,
+        PADDING,
+        subtitle_y,
+        subtitle,
+    );
+
+    card
+}
+
+fn blend(pixel: &mut Rgba<u8>, color: Rgba<u8>, alpha: f32) {
+    for channel in 0..3 {
+        let mixed = pixel[channel] as f32 * (1.0 - alpha) + color[channel] as f32 * alpha;
+        pixel[channel] = mixed.round() as u8;
+    }
+}
+
+fn text_width(font: &FontRef, size: f32, text: &str) -> f32 {
+    let font = font.as_scaled(PxScale::from(size));
+    let mut previous = None;
+    text.chars()
+        .map(|c| {
+            let id = font.glyph_id(c);
+            let kern = previous.map(|prev| font.kern(prev, id)).unwrap_or(0.0);
+            previous = Some(id);
+            kern + font.h_advance(id)
+        })
+        .sum()
+}
+
+/// greedy word wrap, the last line is cut with an ellipsis.
+/// words wider than a line are broken wherever they hit the edge.
+fn wrap(font: &FontRef, size: f32, text: &str, max_width: f32, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let words = text
+        .split_whitespace()
+        .flat_map(|word| break_word(font, size, word, max_width));
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if text_width(font, size, &format!("{} {}", line, word)) <= max_width => {
+                line.push(' ');
+                line.push_str(&word);
+            }
+            _ => lines.push(word),
+        }
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = &mut lines[max_lines - 1];
+        while !last.is_empty() && text_width(font, size, &format!("{}…", last)) > max_width {
+            last.pop();
+        }
+        last.push('…');
+    }
+    lines
+}
+
+fn break_word(font: &FontRef, size: f32, word: &str, max_width: f32) -> Vec<String> {
+    let mut pieces = vec![String::new()];
+    for c in word.chars() {
+        let piece = pieces.last_mut().expect("starts with a piece");
+        if !piece.is_empty() && text_width(font, size, &format!("{}{}", piece, c)) > max_width {
+            pieces.push(c.to_string());
+        } else {
+            piece.push(c);
+        }
+    }
+    pieces
+}
+
+/// draws a single line, `y` is the baseline
+fn draw_text(
+    card: &mut RgbaImage,
+    font: &FontRef,
+    size: f32,
+    color: Rgba<u8>,
+    x: i32,
+    y: i32,
+    text: &str,
+) {
+    let scale = PxScale::from(size);
+    let scaled = font.as_scaled(scale);
+    let mut caret = x as f32;
+    let mut previous = None;
+
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = previous {
+            caret += scaled.kern(prev, id);
+        }
+        previous = Some(id);
+
+        let glyph = id.with_scale_and_position(scale, point(caret, y as f32));
+        caret += scaled.h_advance(id);
+
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= card.width() as i32 || py >= card.height() as i32 {
+                return;
+            }
+            blend(card.get_pixel_mut(px as u32, py as u32), color, coverage);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests;
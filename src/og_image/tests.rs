@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use image::Rgba;
+
+use super::{bold, render, text_width, wrap, BACKGROUND, HEIGHT, PADDING, WHITE, WIDTH};
+
+const SIZE: f32 = 64.0;
+const MAX_WIDTH: f32 = 600.0;
+
+fn lines(text: &str, max_lines: usize) -> Vec<String> {
+    wrap(bold(), SIZE, text, MAX_WIDTH, max_lines)
+}
+
+fn fits(lines: &[String]) -> bool {
+    lines
+        .iter()
+        .all(|line| text_width(bold(), SIZE, line) <= MAX_WIDTH)
+}
+
+#[test]
+fn short_titles_stay_on_one_line() {
+    assert_eq!(lines("Bevy Game Jam", 3), ["Bevy Game Jam"]);
+}
+
+#[test]
+fn words_wrap_at_the_width() {
+    let wrapped = lines("Making a game in rust with the bevy engine", 5);
+    assert!(wrapped.len() > 1);
+    assert!(fits(&wrapped));
+    assert_eq!(
+        wrapped.join(" "),
+        "Making a game in rust with the bevy engine"
+    );
+}
+
+#[test]
+fn overflowing_lines_end_with_an_ellipsis() {
+    let wrapped = lines("one two three four five six seven eight nine ten eleven", 2);
+    assert_eq!(wrapped.len(), 2);
+    assert!(wrapped[1].ends_with('…'));
+    assert!(fits(&wrapped));
+}
+
+#[test]
+fn words_wider_than_a_line_are_broken() {
+    let word = "Donaudampfschifffahrtsgesellschaftskapitänsmütze";
+    let wrapped = lines(word, 5);
+
+    assert!(wrapped.len() > 1);
+    assert!(fits(&wrapped));
+    assert_eq!(wrapped.concat(), word);
+}
+
+#[test]
+fn card_without_cover_has_the_background_and_the_title() {
+    let card = render("Title", "#bevy", None);
+
+    assert_eq!(card.dimensions(), (WIDTH, HEIGHT));
+    assert_eq!(*card.get_pixel(WIDTH - 1, 0), BACKGROUND);
+    // some of the title glyphs are fully covered
+    let title_area = (PADDING as u32..PADDING as u32 + 150)
+        .flat_map(|x| (HEIGHT / 2..HEIGHT - PADDING as u32).map(move |y| (x, y)));
+    assert!(title_area
+        .into_iter()
+        .any(|(x, y)| *card.get_pixel(x, y) == WHITE));
+}
+
+#[test]
+fn card_with_cover_shows_the_cover() {
+    let cover = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/images/fixtures/stripes.png");
+    let card = render("Title", "#bevy", Some(&cover));
+
+    assert_eq!(card.dimensions(), (WIDTH, HEIGHT));
+    assert_ne!(*card.get_pixel(WIDTH - 1, 0), BACKGROUND);
+    assert_ne!(*card.get_pixel(WIDTH - 1, 0), Rgba([0, 0, 0, 255]));
+}
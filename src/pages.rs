@@ -1,77 +1,98 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
 };
-use maud::{html, Markup, PreEscaped};
+use maud::{Markup, PreEscaped};
 
 use super::templates;
-use crate::AppState;
+use crate::{files::ArticleStore, meta::PageMeta, AppState, ErrorResponse};
 
-pub struct PageMeta {
-    pub title: String,
-    pub description: String,
-    pub keywords: String,
-    pub image: Option<String>,
+pub async fn not_found() -> ErrorResponse {
+    ErrorResponse::FileNotFound
 }
 
-pub async fn home(page: Option<Path<String>>, State(state): State<AppState>) -> Response {
-    templates::base(
-        &meta_builder(page.as_ref().map(|p| p.as_str()), &state),
-        &html!(
-            @match page {
-                Some(page) => {
-                    div
-                    hx-get=(format!("/htmx/{}", *page))
-                    hx-target="#main"
-                    hx-trigger="load"
-                    {
-                        div class="loading-spinner" src="static/images/spinner.svg" {}
-                    }
-                },
-                None => {
-                    div
-                    hx-get="/htmx/home"
-                    hx-target="#main"
-                    hx-trigger="load"
-                    {
-                        div class="loading-spinner" src="static/images/spinner.svg" {}
-                    }
-                }
-            }
-        ),
-    )
-    .into_response()
-}
+/// requests from htmx get the bare fragment. everything else, like crawlers, readers without js
+/// and htmx history restores, get the fragment rendered into the full layout.
+/// error fragments are retargeted to `#main`, wherever the failed request wanted to swap.
+pub async fn layout(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let is_fragment = is_htmx(request.headers());
+    let page = request.uri().path().trim_matches('/').to_string();
 
-fn meta_builder(page: Option<&str>, state: &AppState) -> Markup {
-    let meta = page
-        .map(|path| {
-            let split: Option<[&str; 2]> = path.split('/').collect::<Vec<&str>>().try_into().ok();
-            split
-        })
-        .flatten()
-        .map(|[_, slug]| state.articles.find_by_alias(slug))
-        .flatten()
-        .map(|article| article.meta.clone().into())
-        .unwrap_or(PageMeta{
-            title: "Lommix's Blog".into(),
-            description: "Explore the intersection of game development and web development on my blog, featuring in-depth discussions and tutorials on cutting-edge technologies like Rust and Go. Enhance your coding experience with tips and tricks for Neovim, Ai, Game and Webdev.".into(),
-            keywords: "Webdev, Rust, Gamedev, Blog".into(),
-            image: None,
-        });
+    let mut response = next.run(request).await;
+    let is_html = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|mime| mime.starts_with("text/html"))
+        .unwrap_or(false);
+    if !is_html {
+        return response;
+    }
 
-    html!(
-        @if let Some(image) = &meta.image {
-            meta property="og:image" content=(format!("https://lommix.com/{}", image));
+    let is_error = response.status().is_client_error() || response.status().is_server_error();
+    let headers = response.headers_mut();
+    headers.append(header::VARY, HeaderValue::from_static("hx-request"));
+    if is_fragment {
+        if is_error {
+            headers.insert("hx-retarget", HeaderValue::from_static("#main"));
+            headers.insert("hx-reswap", HeaderValue::from_static("innerHTML"));
         }
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(fragment) = to_bytes(body, usize::MAX).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+
+    let page = match is_error {
+        true => None,
+        false => Some(page.as_str()),
+    };
+    let page = templates::base(
+        &meta_builder(page, &*state.articles.read().await),
+        &PreEscaped(String::from_utf8_lossy(&fragment).into_owned()),
+    );
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(page.into_string()))
+}
 
-        title {( meta.title )}
+/// files like media and share cards are no pages, their errors are plain text
+/// instead of an html fragment without layout
+pub async fn plain_errors(response: Response) -> Response {
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return response;
+    }
+
+    let (mut parts, _) = response.into_parts();
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let message = format!(
+        "{} {}",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or("Error")
+    );
+    Response::from_parts(parts, Body::from(message))
+}
+
+fn is_htmx(headers: &HeaderMap) -> bool {
+    headers.contains_key("hx-request") && !headers.contains_key("hx-history-restore-request")
+}
 
-        meta name="title" content=(meta.title);
-        meta name="og:title" content=(meta.title);
-        meta name="description" content=(meta.description);
-        meta name="og:description" content=(meta.description);
-        meta name="keywords" content=(meta.keywords);
-    )
+/// metadata of a public article for `article/{alias}`, the site defaults for anything else
+fn meta_builder(page: Option<&str>, articles: &ArticleStore) -> Markup {
+    let path = format!("/{}", page.unwrap_or_default());
+    page.and_then(|page| page.strip_prefix("article/"))
+        .and_then(|alias| articles.find_by_alias(alias))
+        .filter(|article| article.meta.is_public())
+        .map(PageMeta::from)
+        .unwrap_or_else(|| PageMeta::site(&path))
+        .render()
 }
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use crate::files::Article;
+
+/// bm25 tuning, defaults from the literature
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// term frequency multiplier per field, a hit in the title is worth more than one in the text
+const TITLE_WEIGHT: f32 = 4.0;
+const TAG_WEIGHT: f32 = 3.0;
+const TEASER_WEIGHT: f32 = 2.0;
+
+/// words shown around the first hit of a snippet
+const SNIPPET_BEFORE: usize = 8;
+const SNIPPET_AFTER: usize = 24;
+
+/// in-memory inverted index over all articles of the store
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    postings: HashMap<String, Vec<(usize, f32)>>,
+    doc_len: Vec<f32>,
+    avg_len: f32,
+    texts: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SearchHit {
+    /// position of the article in the store
+    pub article: usize,
+    pub score: f32,
+    pub snippet: Vec<SnippetPart>,
+}
+
+/// part of a snippet, either plain text or a highlighted match
+#[derive(Debug)]
+pub enum SnippetPart {
+    Text(String),
+    Match(String),
+}
+
+impl SearchIndex {
+    pub fn build(articles: &[Article]) -> Self {
+        let mut index = SearchIndex::default();
+
+        for (doc, article) in articles.iter().enumerate() {
+            let text = article
+                .compiled
+                .as_deref()
+                .map(strip_html)
+                .unwrap_or_default();
+
+            let mut terms: HashMap<String, f32> = HashMap::new();
+            let mut add = |field: &str, weight: f32| {
+                for term in tokenize(field) {
+                    *terms.entry(term).or_default() += weight;
+                }
+            };
+
+            add(&article.meta.title, TITLE_WEIGHT);
+            add(&article.meta.teaser, TEASER_WEIGHT);
+            article
+                .meta
+                .tag_set
+                .iter()
+                .for_each(|tag| add(tag, TAG_WEIGHT));
+            add(&text, 1.0);
+
+            index.doc_len.push(terms.values().sum());
+            for (term, tf) in terms {
+                index.postings.entry(term).or_default().push((doc, tf));
+            }
+            index
+                .texts
+                .push(format!("{} {}", article.meta.teaser, text));
+        }
+
+        let docs = index.doc_len.len().max(1) as f32;
+        index.avg_len = index.doc_len.iter().sum::<f32>() / docs;
+        index
+    }
+
+    /// ranks articles against the query. the last query word also matches as prefix,
+    /// so results show up while typing.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let query_terms = tokenize(query).collect::<Vec<_>>();
+        let Some(last) = query_terms.last() else {
+            return Vec::new();
+        };
+
+        let mut matched_terms: Vec<&str> = Vec::new();
+        for (term, _) in self.postings.iter() {
+            let exact = query_terms.contains(term);
+            let prefix = last.len() > 1 && term.starts_with(last.as_str());
+            if exact || prefix {
+                matched_terms.push(term);
+            }
+        }
+
+        let docs = self.doc_len.len() as f32;
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        for term in matched_terms.iter() {
+            let postings = &self.postings[*term];
+            let df = postings.len() as f32;
+            let idf = (1.0 + (docs - df + 0.5) / (df + 0.5)).ln();
+
+            for (doc, tf) in postings {
+                let norm = 1.0 - B + B * self.doc_len[*doc] / self.avg_len;
+                *scores.entry(*doc).or_default() += idf * tf * (K1 + 1.0) / (tf + K1 * norm);
+            }
+        }
+
+        let mut hits = scores
+            .into_iter()
+            .map(|(article, score)| SearchHit {
+                article,
+                score,
+                snippet: snippet(&self.texts[article], &matched_terms),
+            })
+            .collect::<Vec<_>>();
+
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        hits
+    }
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    words(text).map(|(start, end)| text[start..end].to_lowercase())
+}
+
+/// byte ranges of all alphanumeric words in the text
+fn words(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut start = None;
+    text.char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+        .filter_map(move |(i, c)| match (c.is_alphanumeric(), start) {
+            (true, None) => {
+                start = Some(i);
+                None
+            }
+            (false, Some(s)) => {
+                start = None;
+                Some((s, i))
+            }
+            _ => None,
+        })
+}
+
+/// cuts a window around the first matching word and marks all matches in it
+fn snippet(text: &str, terms: &[&str]) -> Vec<SnippetPart> {
+    let words = words(text).collect::<Vec<_>>();
+    let is_match = |(start, end): (usize, usize)| {
+        let word = text[start..end].to_lowercase();
+        terms.iter().any(|term| word == *term)
+    };
+
+    let first = words.iter().position(|w| is_match(*w)).unwrap_or(0);
+    let from = first.saturating_sub(SNIPPET_BEFORE);
+    let to = (first + SNIPPET_AFTER).min(words.len());
+    let Some(window) = words.get(from..to).filter(|w| !w.is_empty()) else {
+        return Vec::new();
+    };
+
+    let mut parts = Vec::new();
+    if from > 0 {
+        parts.push(SnippetPart::Text("… ".into()));
+    }
+
+    let mut cursor = window[0].0;
+    for word in window {
+        if is_match(*word) {
+            parts.push(SnippetPart::Text(text[cursor..word.0].into()));
+            parts.push(SnippetPart::Match(text[word.0..word.1].into()));
+            cursor = word.1;
+        }
+    }
+
+    let end = window[window.len() - 1].1;
+    parts.push(SnippetPart::Text(text[cursor..end].into()));
+    if to < words.len() {
+        parts.push(SnippetPart::Text(" …".into()));
+    }
+    parts
+}
+
+/// plain text of compiled html, for indexing and snippets
+fn strip_html(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                out.push(' ');
+            }
+            _ if !in_tag => out.push(c),
+            _ => (),
+        }
+    }
+
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,115 @@
+use crate::files::{Article, ArticleMeta, ArticleStore, PublishStatus};
+
+use super::{strip_html, SearchHit, SnippetPart};
+
+fn article(alias: &str, title: &str, tags: &[&str], html: &str) -> Article {
+    Article {
+        meta: ArticleMeta {
+            title: title.into(),
+            alias: alias.into(),
+            tag_set: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        },
+        compiled: Some(html.into()),
+        ..Default::default()
+    }
+}
+
+fn store() -> ArticleStore {
+    let mut draft = article(
+        "draft",
+        "Particles everywhere",
+        &["particles"],
+        "<p>particles particles particles</p>",
+    );
+    draft.meta.status = PublishStatus::Draft;
+
+    ArticleStore::from(vec![
+        article(
+            "neovim",
+            "Neovim scripts",
+            &["neovim"],
+            "<p>A few lua scripts. One of them spawns a particle when typing.</p>",
+        ),
+        article(
+            "particles",
+            "Particle systems",
+            &["bevy"],
+            "<p>Spawning a <b>particle</b> on the gpu &amp; moving it with a compute shader.</p>",
+        ),
+        draft,
+    ])
+}
+
+fn aliases(hits: &[(&Article, SearchHit)]) -> Vec<String> {
+    hits.iter()
+        .map(|(article, _)| article.meta.alias.clone())
+        .collect()
+}
+
+#[test]
+fn title_hits_rank_above_text_hits() {
+    let store = store();
+    let hits = store.search("particle", 10);
+
+    assert_eq!(aliases(&hits), ["particles", "neovim"]);
+    assert!(hits[0].1.score > hits[1].1.score);
+}
+
+#[test]
+fn drafts_are_not_found() {
+    assert!(store().search("everywhere", 10).is_empty());
+}
+
+#[test]
+fn last_word_matches_as_prefix() {
+    let store = store();
+
+    assert_eq!(aliases(&store.search("neov", 10)), ["neovim"]);
+    assert_eq!(aliases(&store.search("compute sha", 10)), ["particles"]);
+    // only the last word is a prefix, and only from two characters on
+    assert_eq!(aliases(&store.search("neov bevy", 10)), ["particles"]);
+    assert!(store.search("n", 10).is_empty());
+}
+
+#[test]
+fn snippet_highlights_matches_in_original_case() {
+    let store = store();
+    let hits = store.search("spawning", 10);
+    let [(_, hit)] = hits.as_slice() else {
+        panic!("expected a single hit");
+    };
+
+    let matches = hit
+        .snippet
+        .iter()
+        .filter_map(|part| match part {
+            SnippetPart::Match(word) => Some(word.as_str()),
+            SnippetPart::Text(_) => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(matches, ["Spawning"]);
+
+    let text = hit
+        .snippet
+        .iter()
+        .map(|part| match part {
+            SnippetPart::Match(word) | SnippetPart::Text(word) => word.as_str(),
+        })
+        .collect::<String>();
+    assert_eq!(
+        text,
+        "Spawning a particle on the gpu & moving it with a compute shader"
+    );
+}
+
+#[test]
+fn strip_html_keeps_text_and_decodes_entities() {
+    assert_eq!(
+        strip_html(
+            "<p>a &amp; <b>b</b></p>\n<pre><code>&lt;div class=&quot;x&quot;&gt;</code></pre>"
+        ),
+        "a & b <div class=\"x\">"
+    );
+    assert_eq!(strip_html("one<br>two"), "one two");
+}
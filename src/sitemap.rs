@@ -0,0 +1,70 @@
+use axum::{
+    extract::State,
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+};
+use chrono::NaiveDate;
+use maud::{html, PreEscaped};
+
+use crate::{
+    feed::XML_DECLARATION,
+    files::{tag_url, Article},
+    meta::base_url,
+    AppState,
+};
+
+/// sitemap of all component pages and articles
+pub async fn sitemap(State(state): State<AppState>, pages: Vec<&'static str>) -> Response {
+    let articles = state.articles.read().await;
+    let latest = articles.listed().map(last_modified).max();
+
+    let sitemap = html!(
+        (PreEscaped(XML_DECLARATION))
+        urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9" {
+            @for page in pages {
+                url {
+                    loc { (base_url()) (page) }
+                    @if let Some(date) = latest.filter(|_| page == "/" || page == "/blog") {
+                        lastmod { (date.format("%Y-%m-%d")) }
+                    }
+                }
+            }
+            @for (tag, _) in articles.tags() {
+                url {
+                    loc { (format!("{}{}", base_url(), tag_url(tag))) }
+                }
+            }
+            @for article in articles.listed() {
+                url {
+                    loc { (format!("{}/article/{}", base_url(), article.meta.alias)) }
+                    lastmod { (last_modified(article).format("%Y-%m-%d")) }
+                }
+            }
+        }
+    );
+
+    ([(CONTENT_TYPE, "application/xml")], sitemap.into_string()).into_response()
+}
+
+/// serves the file from `ROBOTS_FILE` or a default, pointing crawlers to the sitemap
+pub async fn robots() -> Response {
+    let robots_path = std::env::var("ROBOTS_FILE").unwrap_or("robots.txt".into());
+    let robots = match tokio::fs::read_to_string(robots_path).await {
+        Ok(robots) => robots,
+        Err(_) => format!(
+            "User-agent: *\nAllow: /\nDisallow: /htmx/\nDisallow: /admin/\n\nSitemap: {}/sitemap.xml\n",
+            base_url()
+        ),
+    };
+
+    ([(CONTENT_TYPE, "text/plain")], robots).into_response()
+}
+
+/// the later one of publish date and last edit of the article source
+pub fn last_modified(article: &Article) -> NaiveDate {
+    std::fs::metadata(&article.source)
+        .and_then(|meta| meta.modified())
+        .map(|modified| chrono::DateTime::<chrono::Utc>::from(modified).date_naive())
+        .map(|modified| modified.max(article.meta.published_at))
+        .unwrap_or(article.meta.published_at)
+}
@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+
+use crate::db::{self, DailyCount, DbPool};
+
+#[derive(Debug, Clone, Copy, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    Day,
+    Week,
+    Month,
+    Page,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    /// first day, defaults to 30 days before `--to`
+    #[arg(long)]
+    from: Option<NaiveDate>,
+    /// last day, defaults to today
+    #[arg(long)]
+    to: Option<NaiveDate>,
+    /// only targets containing the pattern, `*` is a wildcard
+    #[arg(long)]
+    page: Option<String>,
+    #[arg(long, value_enum, default_value_t = GroupBy::Day)]
+    group_by: GroupBy,
+    /// only the N targets with the most counts
+    #[arg(long)]
+    top: Option<usize>,
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+}
+
+#[derive(Debug, Serialize)]
+struct Row {
+    /// the day, week or month, empty when grouped by page
+    period: String,
+    kind: String,
+    target: String,
+    count: i64,
+    sessions: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Totals {
+    count: i64,
+    /// distinct sessions summed per day, so returning visitors count once per day.
+    /// none when filtered by page, they are only known for the whole site.
+    sessions: Option<i64>,
+    by_kind: BTreeMap<String, i64>,
+}
+
+/// visits of an article compared to the period of the same length right before
+#[derive(Debug, Serialize)]
+struct Trend {
+    target: String,
+    current: i64,
+    previous: i64,
+    /// in percent, none without previous visits
+    change: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    from: String,
+    to: String,
+    days: i64,
+    group_by: GroupBy,
+    rows: Vec<Row>,
+    totals: Totals,
+    trends: Vec<Trend>,
+}
+
+pub async fn print(pool: &DbPool, args: StatsArgs) -> anyhow::Result<()> {
+    let to = args.to.unwrap_or(Utc::now().date_naive());
+    let from = args.from.unwrap_or(to - Duration::days(29));
+    if from > to {
+        anyhow::bail!("--from {} is after --to {}", from, to);
+    }
+
+    // the previous period of the same length, for the trends
+    let days = (to - from).num_days() + 1;
+    let previous_from = from - Duration::days(days);
+    let counts = db::daily_counts(pool, previous_from, to, args.page.clone()).await?;
+    let (current, previous): (Vec<_>, Vec<_>) =
+        counts.into_iter().partition(|count| count.day >= from);
+
+    let sessions = match args.page {
+        Some(_) => None,
+        None => Some(
+            db::daily_sessions(pool, from, to)
+                .await?
+                .into_iter()
+                .map(|(_, sessions)| sessions)
+                .sum(),
+        ),
+    };
+
+    // `--top` only shortens the rows, totals and trends cover everything
+    let report = Report {
+        from: from.to_string(),
+        to: to.to_string(),
+        days,
+        group_by: args.group_by,
+        rows: rows(&top_targets(current.clone(), args.top), args.group_by),
+        totals: totals(&current, sessions),
+        trends: trends(&current, &previous),
+    };
+
+    match args.format {
+        Format::Table => print_table(&report),
+        Format::Csv => print_csv(&report),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    Ok(())
+}
+
+/// keeps the counts of the `top` targets with the highest total
+fn top_targets(counts: Vec<DailyCount>, top: Option<usize>) -> Vec<DailyCount> {
+    let Some(top) = top else {
+        return counts;
+    };
+
+    let mut totals = BTreeMap::<(&str, &str), i64>::new();
+    for count in counts.iter() {
+        *totals.entry((&count.kind, &count.target)).or_default() += count.count;
+    }
+    let mut ranked = totals.into_iter().collect::<Vec<_>>();
+    ranked.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let keep = ranked
+        .into_iter()
+        .take(top)
+        .map(|((kind, target), _)| (kind.to_string(), target.to_string()))
+        .collect::<Vec<_>>();
+
+    counts
+        .into_iter()
+        .filter(|count| keep.contains(&(count.kind.clone(), count.target.clone())))
+        .collect()
+}
+
+fn period(day: NaiveDate, group_by: GroupBy) -> String {
+    match group_by {
+        GroupBy::Day => day.format("%Y-%m-%d").to_string(),
+        GroupBy::Week => {
+            let week = day.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+        GroupBy::Month => day.format("%Y-%m").to_string(),
+        GroupBy::Page => String::new(),
+    }
+}
+
+fn rows(counts: &[DailyCount], group_by: GroupBy) -> Vec<Row> {
+    let mut grouped = BTreeMap::<(String, &str, &str), (i64, Option<i64>)>::new();
+    for count in counts {
+        let key = (period(count.day, group_by), &*count.kind, &*count.target);
+        let (total, sessions) = grouped.entry(key).or_default();
+        *total += count.count;
+        if let Some(count) = count.sessions {
+            *sessions = Some(sessions.unwrap_or(0) + count);
+        }
+    }
+
+    let mut rows = grouped
+        .into_iter()
+        .map(|((period, kind, target), (count, sessions))| Row {
+            period,
+            kind: kind.to_string(),
+            target: target.to_string(),
+            count,
+            sessions,
+        })
+        .collect::<Vec<_>>();
+
+    // the newest period first, the most counted target first within it
+    rows.sort_by(|a, b| b.period.cmp(&a.period).then(b.count.cmp(&a.count)));
+    rows
+}
+
+fn totals(counts: &[DailyCount], sessions: Option<i64>) -> Totals {
+    let mut totals = Totals {
+        sessions,
+        ..Default::default()
+    };
+    for count in counts {
+        totals.count += count.count;
+        *totals.by_kind.entry(count.kind.clone()).or_default() += count.count;
+    }
+    totals
+}
+
+fn trends(current: &[DailyCount], previous: &[DailyCount]) -> Vec<Trend> {
+    let visits = |counts: &[DailyCount]| {
+        let mut visits = BTreeMap::<String, i64>::new();
+        for count in counts.iter().filter(|count| count.kind == "visit") {
+            *visits.entry(count.target.clone()).or_default() += count.count;
+        }
+        visits
+    };
+    let previous = visits(previous);
+
+    let mut trends = visits(current)
+        .into_iter()
+        .map(|(target, current)| {
+            let before = previous.get(&target).copied().unwrap_or(0);
+            Trend {
+                change: (before > 0).then(|| (current - before) as f64 / before as f64 * 100.0),
+                target,
+                current,
+                previous: before,
+            }
+        })
+        .collect::<Vec<_>>();
+    trends.sort_by_key(|trend| std::cmp::Reverse(trend.current));
+    trends
+}
+
+fn print_table(report: &Report) {
+    println!("stats from {} to {}", report.from, report.to);
+    println!();
+
+    let period_width = match report.group_by {
+        GroupBy::Page => 0,
+        _ => 12,
+    };
+    println!(
+        "{:<w$.w$}{:<7.7}{:<35.35}{:>8} {:>9}",
+        "period",
+        "kind",
+        "target",
+        "count",
+        "sessions",
+        w = period_width,
+    );
+    for row in report.rows.iter() {
+        println!(
+            "{:<w$.w$}{:<7.7}{:<35.35}{:>8} {:>9}",
+            row.period,
+            row.kind,
+            row.target,
+            row.count,
+            row.sessions
+                .map(|sessions| sessions.to_string())
+                .unwrap_or("-".into()),
+            w = period_width,
+        );
+    }
+
+    println!();
+    match report.totals.sessions {
+        Some(sessions) => println!(
+            "total: {} ({} daily sessions)",
+            report.totals.count, sessions
+        ),
+        None => println!("total: {}", report.totals.count),
+    }
+    for (kind, count) in report.totals.by_kind.iter() {
+        println!("  {:<7.7}{:>8}", kind, count);
+    }
+
+    if report.trends.is_empty() {
+        return;
+    }
+    println!();
+    println!("visits compared to the {} days before", report.days);
+    for trend in report.trends.iter() {
+        let change = trend
+            .change
+            .map(|change| format!("{:+.0}%", change))
+            .unwrap_or("new".into());
+        println!(
+            "  {:<35.35}{:>8} {:>8} {:>8}",
+            trend.target, trend.current, trend.previous, change
+        );
+    }
+}
+
+/// only the rows, csv has no place for totals and trends
+fn print_csv(report: &Report) {
+    println!("period,kind,target,count,sessions");
+    for row in report.rows.iter() {
+        println!(
+            "{},{},{},{},{}",
+            csv_field(&row.period),
+            csv_field(&row.kind),
+            csv_field(&row.target),
+            row.count,
+            row.sessions
+                .map(|sessions| sessions.to_string())
+                .unwrap_or_default(),
+        );
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,141 @@
+use std::collections::BTreeSet;
+
+use chrono::NaiveDate;
+
+use super::{csv_field, rows, top_targets, totals, trends, GroupBy};
+use crate::db::DailyCount;
+
+fn count(day: &str, kind: &str, target: &str, count: i64, sessions: Option<i64>) -> DailyCount {
+    DailyCount {
+        day: day.parse::<NaiveDate>().unwrap(),
+        kind: kind.into(),
+        target: target.into(),
+        count,
+        sessions,
+    }
+}
+
+/// sunday of 2023-W52, then monday and tuesday of 2024-W01
+fn counts() -> Vec<DailyCount> {
+    vec![
+        count("2023-12-31", "visit", "boids", 4, Some(2)),
+        count("2024-01-01", "visit", "boids", 3, Some(3)),
+        count("2024-01-01", "visit", "home", 10, None),
+        count("2024-01-02", "play", "boid", 2, Some(1)),
+        count("2024-01-02", "visit", "boids", 1, Some(1)),
+    ]
+}
+
+fn summary(group_by: GroupBy) -> Vec<(String, String, String, i64, Option<i64>)> {
+    rows(&counts(), group_by)
+        .into_iter()
+        .map(|row| (row.period, row.kind, row.target, row.count, row.sessions))
+        .collect()
+}
+
+fn row(
+    period: &str,
+    kind: &str,
+    target: &str,
+    count: i64,
+    sessions: Option<i64>,
+) -> (String, String, String, i64, Option<i64>) {
+    (period.into(), kind.into(), target.into(), count, sessions)
+}
+
+#[test]
+fn rows_are_grouped_by_iso_week() {
+    assert_eq!(
+        summary(GroupBy::Week),
+        vec![
+            row("2024-W01", "visit", "home", 10, None),
+            row("2024-W01", "visit", "boids", 4, Some(4)),
+            row("2024-W01", "play", "boid", 2, Some(1)),
+            row("2023-W52", "visit", "boids", 4, Some(2)),
+        ]
+    );
+}
+
+#[test]
+fn rows_are_grouped_by_month() {
+    assert_eq!(
+        summary(GroupBy::Month),
+        vec![
+            row("2024-01", "visit", "home", 10, None),
+            row("2024-01", "visit", "boids", 4, Some(4)),
+            row("2024-01", "play", "boid", 2, Some(1)),
+            row("2023-12", "visit", "boids", 4, Some(2)),
+        ]
+    );
+}
+
+#[test]
+fn rows_are_grouped_by_page_without_period() {
+    assert_eq!(
+        summary(GroupBy::Page),
+        vec![
+            row("", "visit", "home", 10, None),
+            row("", "visit", "boids", 8, Some(6)),
+            row("", "play", "boid", 2, Some(1)),
+        ]
+    );
+}
+
+#[test]
+fn top_keeps_the_most_counted_targets() {
+    let kept = top_targets(counts(), Some(2));
+    assert_eq!(kept.len(), 4);
+    let targets = kept
+        .iter()
+        .map(|count| (count.kind.as_str(), count.target.as_str()))
+        .collect::<BTreeSet<_>>();
+    assert_eq!(
+        targets,
+        BTreeSet::from([("visit", "boids"), ("visit", "home")])
+    );
+
+    assert_eq!(top_targets(counts(), None).len(), counts().len());
+}
+
+#[test]
+fn totals_count_every_target() {
+    let totals = totals(&counts(), Some(5));
+    assert_eq!(totals.count, 20);
+    assert_eq!(totals.sessions, Some(5));
+    assert_eq!(totals.by_kind["visit"], 18);
+    assert_eq!(totals.by_kind["play"], 2);
+}
+
+#[test]
+fn trends_compare_visits_with_the_previous_period() {
+    let previous = vec![
+        count("2023-12-01", "visit", "boids", 4, None),
+        count("2023-12-01", "play", "home", 7, None),
+    ];
+    let trends = trends(&counts(), &previous);
+
+    let summary = trends
+        .iter()
+        .map(|trend| {
+            (
+                trend.target.as_str(),
+                trend.current,
+                trend.previous,
+                trend.change,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [("home", 10, 0, None), ("boids", 8, 4, Some(100.0))]
+    );
+}
+
+#[test]
+fn csv_fields_are_quoted_when_needed() {
+    assert_eq!(csv_field("boids"), "boids");
+    assert_eq!(csv_field(""), "");
+    assert_eq!(csv_field("a,b"), "\"a,b\"");
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+}
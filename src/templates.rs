@@ -1,16 +1,24 @@
+use axum::http::StatusCode;
 use maud::{html, Markup, DOCTYPE};
 
+use crate::htmx::bundle;
+
 /// layout template
 pub fn base(meta: &Markup, content: &Markup) -> Markup {
     html! {
             (DOCTYPE)
             head {
+                // must be within the first 1024 bytes of the document
+                meta charset="utf-8";
                 link rel="stylesheet" href="/static/main42.css";
-                link rel="stylesheet" href="/htmx/style.css" {}
+                link rel="stylesheet" href=(bundle::style_url()) {}
+                link rel="stylesheet" href="/highlight.css" {}
 
                 (meta)
 
-                meta charset="utf-8";
+                link rel="alternate" type="application/rss+xml" title="Lommix's Blog" href="/feed.xml";
+                link rel="alternate" type="application/atom+xml" title="Lommix's Blog" href="/atom.xml";
+
                 meta name="author" content="lommix";
                 meta name="viewport" content="width=device-width, initial-scale=1.0";
 
@@ -22,15 +30,32 @@ pub fn base(meta: &Markup, content: &Markup) -> Markup {
                 (footer())
 
                 script src="/static/js/wasm_frame.js" type="module"{}
-                script src="/static/js/highlight.min.js" {}
                 script src="/static/js/htmx.min.js"{}
-                script src="/htmx/script.js" type="module" {}
+                script src=(bundle::script_url()) type="module" {}
                 script src="/static/main.js" type="module" {}
 
             }
     }
 }
 
+/// error template, rendered into the layout or swapped into `#main` by htmx
+pub fn error(status: StatusCode) -> Markup {
+    let message = match status {
+        StatusCode::NOT_FOUND => "This page does not exist, or it moved somewhere else.",
+        StatusCode::UNAUTHORIZED => "You are not allowed to see this page.",
+        _ => "Something went wrong on my side. Please try again later.",
+    };
+
+    html! {
+        div class="error-page" {
+            h1 { (status.as_u16()) " " (status.canonical_reason().unwrap_or("Error")) }
+            hr {}
+            p { (message) }
+            a class="error-home" hx-get="/htmx/home" hx-push-url="/" hx-target="#main" href="/" { "Back to the start page" }
+        }
+    }
+}
+
 /// header template
 pub fn header() -> Markup {
     html! {
@@ -0,0 +1,158 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+use crate::{
+    files::{scan_article_dir, Article},
+    markdown,
+};
+
+/// a problem with an article directory, found while loading or checking the blog
+#[derive(Debug)]
+pub enum ValidationError {
+    InvalidMeta { path: PathBuf, reason: String },
+    InvalidDate { path: PathBuf, value: String },
+    MissingMeta(PathBuf),
+    MissingContent(PathBuf),
+    MissingTitle(PathBuf),
+    MissingAlias(PathBuf),
+    DuplicateAlias { alias: String, dirs: Vec<PathBuf> },
+    MissingMedia { dir: PathBuf, reference: String },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::InvalidMeta { path, reason } => {
+                write!(f, "{:?}: invalid meta.ron: {}", path, reason)
+            }
+            ValidationError::InvalidDate { path, value } => {
+                write!(
+                    f,
+                    "{:?}: invalid published date '{}', expected dd.mm.yyyy",
+                    path, value
+                )
+            }
+            ValidationError::MissingMeta(dir) => write!(f, "{:?}: missing meta.ron", dir),
+            ValidationError::MissingContent(dir) => write!(f, "{:?}: missing content.md", dir),
+            ValidationError::MissingTitle(dir) => write!(f, "{:?}: missing title", dir),
+            ValidationError::MissingAlias(dir) => write!(f, "{:?}: missing alias", dir),
+            ValidationError::DuplicateAlias { alias, dirs } => {
+                write!(f, "duplicate alias '{}' in {:?}", alias, dirs)
+            }
+            ValidationError::MissingMedia { dir, reference } => {
+                write!(
+                    f,
+                    "{:?}: referenced media '{}' does not exist",
+                    dir, reference
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// checks every article below `root` and returns all problems found
+pub fn validate(root: &Path) -> anyhow::Result<Vec<ValidationError>> {
+    let mut articles = Vec::new();
+    let mut errors = Vec::new();
+    scan_blog_path(root, &mut articles, &mut errors)?;
+
+    let mut aliases: BTreeMap<&str, Vec<&Article>> = BTreeMap::new();
+    articles
+        .iter()
+        .filter(|article| !article.meta.alias.is_empty())
+        .for_each(|article| {
+            aliases
+                .entry(article.meta.alias.as_str())
+                .or_default()
+                .push(article)
+        });
+
+    for (alias, list) in aliases.iter().filter(|(_, list)| list.len() > 1) {
+        errors.push(ValidationError::DuplicateAlias {
+            alias: alias.to_string(),
+            dirs: list.iter().map(|article| article.dir.clone()).collect(),
+        });
+    }
+
+    for article in articles.iter() {
+        let content = std::fs::read_to_string(&article.source).unwrap_or_default();
+        let cover = article.meta.cover.trim_start_matches('/');
+
+        media_references(&content, article.meta.markdown.options())
+            .into_iter()
+            .chain(cover.strip_prefix("media/").map(str::to_string))
+            .filter(|reference| !media_exists(&aliases, reference))
+            .for_each(|reference| {
+                errors.push(ValidationError::MissingMedia {
+                    dir: article.dir.clone(),
+                    reference: format!("/media/{}", reference),
+                })
+            });
+
+        // relative links may point anywhere, so they are only worth a warning
+        markdown::compile(&content, &article.meta.markdown, &article.media_context())
+            .unresolved
+            .into_iter()
+            .for_each(|link| tracing::warn!("{:?}: unresolved link '{}'", article.dir, link));
+    }
+
+    Ok(errors)
+}
+
+fn scan_blog_path(
+    path: &Path,
+    articles: &mut Vec<Article>,
+    errors: &mut Vec<ValidationError>,
+) -> anyhow::Result<()> {
+    let mut dirs = std::fs::read_dir(path)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    for dir in dirs {
+        scan_blog_path(&dir, articles, errors)?;
+    }
+
+    let (article, article_errors) = scan_article_dir(path)?;
+    if article.source.exists() || !article_errors.is_empty() {
+        articles.push(article);
+    }
+    errors.extend(article_errors);
+    Ok(())
+}
+
+/// finds all links and images pointing to `/media/{alias}/{file}` in a markdown document
+fn media_references(content: &str, options: Options) -> Vec<String> {
+    Parser::new_ext(content, options)
+        .filter_map(|event| match event {
+            Event::Start(Tag::Link(_, dest, _) | Tag::Image(_, dest, _)) => Some(dest),
+            _ => None,
+        })
+        .filter_map(|dest| {
+            let reference = dest.strip_prefix("/media/")?;
+            let end = reference.find(['?', '#']).unwrap_or(reference.len());
+            Some(reference[..end].to_string())
+        })
+        .collect()
+}
+
+fn media_exists(aliases: &BTreeMap<&str, Vec<&Article>>, reference: &str) -> bool {
+    let Some((alias, file)) = reference.split_once('/') else {
+        return false;
+    };
+
+    aliases
+        .get(alias)
+        .map(|list| list.iter().any(|article| article.files.contains_key(file)))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,48 @@
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::Options;
+
+use super::{media_references, validate};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("src/validation/fixtures")
+}
+
+/// the errors found in the fixtures, with paths relative to the fixture dir
+fn fixture_errors() -> Vec<String> {
+    let root = format!("{}/", fixtures().display());
+    validate(&fixtures())
+        .unwrap()
+        .iter()
+        .map(|err| err.to_string().replace(&root, ""))
+        .collect()
+}
+
+#[test]
+fn every_problem_is_reported_in_order() {
+    assert_eq!(
+        fixture_errors(),
+        [
+            r#""bad_date/meta.ron": invalid published date '2024-01-01', expected dd.mm.yyyy"#,
+            r#""bad_ron/meta.ron": invalid meta.ron: 4:5: Expected comma"#,
+            r#""missing_title_alias": missing title"#,
+            r#""missing_title_alias": missing alias"#,
+            r#"duplicate alias 'duplicate' in ["duplicate_a", "duplicate_b"]"#,
+            r#""missing_media": referenced media '/media/missing_media/gone.png' does not exist"#,
+            r#""missing_media": referenced media '/media/duplicate/notes.png' does not exist"#,
+            r#""missing_media": referenced media '/media/missing_media/cover.png' does not exist"#,
+        ]
+    );
+}
+
+#[test]
+fn media_references_are_link_and_image_destinations() {
+    let content = "served below /media/ or `/media/code.png`\n\n\
+        ![a](/media/a/one.png) [b](/media/b/two.png?raw=1) [c](https://example.com/media/c/three.png)\n\n\
+        <img src=\"/media/html/four.png\">";
+
+    assert_eq!(
+        media_references(content, Options::empty()),
+        ["a/one.png", "b/two.png"]
+    );
+}
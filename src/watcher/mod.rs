@@ -0,0 +1,79 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use crate::files::{ArticleStore, SharedArticleStore};
+
+/// time to collect events, before reloading. editors tend to write multiple times per save.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// watches the blog directory and reloads changed articles in place.
+/// the returned watcher must be kept alive, dropping it stops watching.
+pub fn watch_articles(
+    store: SharedArticleStore,
+    root: PathBuf,
+) -> anyhow::Result<RecommendedWatcher> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<PathBuf>();
+
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) => event.paths.into_iter().for_each(|path| {
+                _ = tx.send(path);
+            }),
+            Err(err) => tracing::error!("article watcher error: {}", err),
+        })?;
+
+    let canonical_root = root.canonicalize()?;
+    watcher.watch(&canonical_root, RecursiveMode::Recursive)?;
+
+    tokio::spawn(async move {
+        while let Some(path) = rx.recv().await {
+            let mut changed = HashSet::from([path]);
+            tokio::time::sleep(DEBOUNCE).await;
+            while let Ok(path) = rx.try_recv() {
+                changed.insert(path);
+            }
+
+            let dirs = {
+                let store = store.read().await;
+                changed
+                    .iter()
+                    .filter_map(|path| {
+                        let relative = path.strip_prefix(&canonical_root).ok()?;
+                        article_dir(&root, &root.join(relative), |dir| store.contains_dir(dir))
+                    })
+                    .collect::<HashSet<_>>()
+            };
+
+            // compiling takes a while, requests only wait for the swap
+            for dir in dirs {
+                match ArticleStore::load(&dir).await {
+                    Ok(article) => {
+                        store.write().await.replace(&dir, article);
+                        tracing::info!("reloaded article {:?}", dir);
+                    }
+                    Err(err) => tracing::error!("failed to reload article {:?}: {}", dir, err),
+                }
+            }
+        }
+    });
+
+    Ok(watcher)
+}
+
+/// finds the article directory a changed path belongs to.
+/// either a directory already known to the store or one containing a `meta.ron`.
+fn article_dir(root: &Path, path: &Path, known: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    path.ancestors()
+        .take_while(|dir| *dir != root && dir.starts_with(root))
+        .find(|dir| known(dir) || dir.join("meta.ron").is_file())
+        .map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,94 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::sync::RwLock;
+
+use crate::files::ArticleStore;
+
+use super::{article_dir, watch_articles};
+
+/// a fresh blog directory with two articles
+fn blog_dir(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("watcher-test-{}-{}", std::process::id(), name));
+    _ = std::fs::remove_dir_all(&root);
+    for alias in ["first", "second"] {
+        write_article(&root.join("2024").join(alias), alias, "initial");
+    }
+    root
+}
+
+fn write_article(dir: &Path, alias: &str, content: &str) {
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(
+        dir.join("meta.ron"),
+        format!(
+            r#"(title: "{alias}", alias: "{alias}", cover: "", teaser: "", published: "01.01.2024")"#
+        ),
+    )
+    .unwrap();
+    std::fs::write(dir.join("content.md"), content).unwrap();
+}
+
+async fn compiled(store: &RwLock<ArticleStore>, alias: &str) -> String {
+    store
+        .read()
+        .await
+        .find_by_alias(alias)
+        .and_then(|article| article.compiled.clone())
+        .unwrap_or_default()
+}
+
+#[test]
+fn changed_files_map_to_their_article_dir() {
+    let root = blog_dir("map");
+    let article = root.join("2024/first");
+
+    let found = article_dir(&root, &article.join("content.md"), |_| false);
+    assert_eq!(found, Some(article.clone()));
+
+    // a removed article is only found through the store
+    let removed = root.join("2024/gone");
+    assert_eq!(
+        article_dir(&root, &removed.join("content.md"), |_| false),
+        None
+    );
+    assert_eq!(
+        article_dir(&root, &removed.join("content.md"), |dir| dir == removed),
+        Some(removed)
+    );
+
+    assert_eq!(article_dir(&root, &root.join("2024"), |_| false), None);
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn only_the_changed_article_is_rebuilt() {
+    let root = blog_dir("reload");
+    let store = Arc::new(RwLock::new(
+        ArticleStore::from_dir(root.clone()).await.unwrap(),
+    ));
+    assert!(compiled(&store, "first").await.contains("initial"));
+
+    // changed before watching, so only a reload of `second` would pick it up
+    std::fs::write(root.join("2024/second/content.md"), "unnoticed").unwrap();
+
+    let _watcher = watch_articles(store.clone(), root.clone()).unwrap();
+    std::fs::write(root.join("2024/first/content.md"), "changed").unwrap();
+
+    let mut reloaded = false;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        if compiled(&store, "first").await.contains("changed") {
+            reloaded = true;
+            break;
+        }
+    }
+
+    assert!(reloaded, "first was not reloaded");
+    assert!(compiled(&store, "second").await.contains("initial"));
+    assert_eq!(store.read().await.count(), 2);
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -17,9 +17,16 @@ document.addEventListener("DOMContentLoaded", function () {
     });
 
     document.body.addEventListener("htmx:afterSwap", (ev) => {
-        hljs.highlightAll();
         hook_interaction();
     });
+
+    // error pages are sent retargeted to #main, htmx does not swap error responses by default
+    document.body.addEventListener("htmx:beforeSwap", (ev) => {
+        if (ev.detail.xhr.status >= 400 && ev.detail.xhr.getResponseHeader("HX-Retarget")) {
+            ev.detail.shouldSwap = true;
+            ev.detail.isError = false;
+        }
+    });
 });
 
 function hook_interaction() {
@@ -1,2 +1,2 @@
 #!/bin/bash
-cargo-watch -x 'run serve'
+cargo-watch -i blog -x 'run serve'